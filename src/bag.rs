@@ -0,0 +1,132 @@
+//! Collecting the diagnostics of several validation passes
+
+use crate::{report, Diagnostic, Severity, Theme};
+
+/// Diagnostics gathered from any number of checks, counted by severity
+///
+/// A bag extends from the `Vec<Diagnostic>` each check returns, or collects
+/// straight from an iterator, so whole-contract validation reads as one
+/// expression. [`DiagnosticBag::render`] prints everything as a single
+/// report.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{check_forbidden_globals, validate_imports, DiagnosticBag, Theme};
+///
+/// let code = "import { kv } from 'tana/kvs';\nwindow.alert(1);";
+/// let mut bag = DiagnosticBag::new();
+/// bag.extend(validate_imports(code, &["tana/kv"]));
+/// bag.extend(check_forbidden_globals(code, &["window"]));
+///
+/// assert!(bag.has_errors());
+/// assert!(bag.render(&Theme::default()).ends_with("= 2 errors found\n"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticBag {
+    diagnostics: Vec<Diagnostic>,
+    /// Number of diagnostics of each severity, indexed by `Severity as usize`
+    counts: [usize; 4],
+}
+
+impl DiagnosticBag {
+    /// Create an empty bag
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one diagnostic
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.counts[diagnostic.severity as usize] += 1;
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Number of diagnostics of `severity`
+    pub fn count(&self, severity: Severity) -> usize {
+        self.counts[severity as usize]
+    }
+
+    /// Whether any diagnostic is an error
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Total number of diagnostics
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    /// Whether the bag holds no diagnostics
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// The diagnostics in the order they were added
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Take the diagnostics out of the bag
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    /// Render every diagnostic as one report drawn with `theme`
+    ///
+    /// The report is laid out as by [`format_report`](crate::format_report).
+    pub fn render(&self, theme: &Theme) -> String {
+        report::format_report_with_theme(&self.diagnostics, theme)
+    }
+}
+
+impl Extend<Diagnostic> for DiagnosticBag {
+    fn extend<I: IntoIterator<Item = Diagnostic>>(&mut self, diagnostics: I) {
+        for diagnostic in diagnostics {
+            self.push(diagnostic);
+        }
+    }
+}
+
+impl FromIterator<Diagnostic> for DiagnosticBag {
+    fn from_iter<I: IntoIterator<Item = Diagnostic>>(diagnostics: I) -> Self {
+        let mut bag = Self::new();
+        bag.extend(diagnostics);
+        bag
+    }
+}
+
+impl IntoIterator for DiagnosticBag {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_console_usage, validate_imports};
+
+    #[test]
+    fn test_bag_from_two_checks() {
+        let code = "import { kv } from 'tana/kv';\nconsole.log(kv);";
+        let mut bag: DiagnosticBag = validate_imports(code, &["tana/kv"]).into_iter().collect();
+        assert!(bag.is_empty() && !bag.has_errors());
+
+        bag.extend(check_console_usage(code));
+        assert_eq!(bag.len(), 1);
+        assert_eq!(bag.count(Severity::Warning), 1);
+        assert!(!bag.has_errors());
+
+        bag.extend(validate_imports("import 'lodash';", &["tana/kv"]));
+        assert!(bag.has_errors());
+
+        let rendered = bag.render(&Theme::ascii());
+        assert!(rendered.starts_with("\nValidation Error\n"));
+        assert!(rendered.contains("[warning] Console Usage"));
+        assert!(rendered.contains("[error] Invalid Import"));
+        assert!(rendered.ends_with("= 1 error, 1 warning found\n"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Running every source check in one pass
+
+use super::{
+    check_console_usage, check_entry_point, check_forbidden_globals, check_kv_keys,
+    check_top_level_await, validate_imports,
+};
+use crate::{dedupe, Diagnostic};
+
+/// What [`validate_all`] checks a contract against
+///
+/// The default configuration allows no imports, forbids no globals, and
+/// requires no entry point.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationConfig {
+    /// Modules a contract may import, e.g. `tana/core`
+    pub allowed_modules: Vec<String>,
+    /// Globals the sandbox lacks, e.g. `window` or `process`
+    pub forbidden_globals: Vec<String>,
+    /// Export the runtime calls, `"default"` or a name such as `"handler"`;
+    /// `None` skips the entry-point check
+    pub entry_point: Option<String>,
+}
+
+/// Run every source check on `code` and return what they found, in order
+///
+/// Combines [`validate_imports`], [`check_forbidden_globals`],
+/// [`check_console_usage`], [`check_kv_keys`], [`check_top_level_await`]
+/// and, when `config` names one, [`check_entry_point`]. The diagnostics are
+/// sorted by position and repeats are dropped with [`dedupe`].
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{validate_all, ValidationConfig};
+///
+/// let config = ValidationConfig {
+///     allowed_modules: vec!["tana/core".to_string()],
+///     forbidden_globals: vec!["process".to_string()],
+///     entry_point: Some("handler".to_string()),
+/// };
+/// let code = "import { kv } from 'tana/kv';\nexport function handler() {}";
+///
+/// let diagnostics = validate_all(code, &config);
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].kind, "Invalid Import");
+/// ```
+pub fn validate_all(code: &str, config: &ValidationConfig) -> Vec<Diagnostic> {
+    let allowed: Vec<&str> = config.allowed_modules.iter().map(String::as_str).collect();
+    let forbidden: Vec<&str> = config
+        .forbidden_globals
+        .iter()
+        .map(String::as_str)
+        .collect();
+
+    let mut diagnostics = validate_imports(code, &allowed);
+    diagnostics.extend(check_forbidden_globals(code, &forbidden));
+    diagnostics.extend(check_console_usage(code));
+    diagnostics.extend(check_kv_keys(code));
+    diagnostics.extend(check_top_level_await(code));
+    if let Some(entry_point) = &config.entry_point {
+        diagnostics.extend(check_entry_point(code, entry_point));
+    }
+
+    diagnostics.sort();
+    dedupe(&mut diagnostics);
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+
+    fn config() -> ValidationConfig {
+        ValidationConfig {
+            allowed_modules: vec!["tana/core".to_string(), "tana/kv".to_string()],
+            forbidden_globals: vec!["window".to_string(), "process".to_string()],
+            entry_point: Some("handler".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_one_of_each_problem_in_position_order() {
+        let code = "\
+import { tx } from 'tana/txs';
+function handler() {
+  console.log(process.env.KEY);
+}
+";
+        let diagnostics = validate_all(code, &config());
+
+        let found: Vec<(&str, usize, usize)> = diagnostics
+            .iter()
+            .map(|d| (&*d.kind, d.span.line, d.span.col))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Invalid Import", 1, 21),
+                ("Console Usage", 3, 3),
+                ("Forbidden Global", 3, 15),
+                ("Missing Export", 4, 2),
+            ]
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_clean_contract_passes() {
+        let code = "import { kv } from 'tana/kv';\nexport function handler() {\n  return kv;\n}";
+
+        assert!(validate_all(code, &config()).is_empty());
+
+        // Without an entry point configured, its absence isn't reported
+        let config = ValidationConfig {
+            entry_point: None,
+            ..config()
+        };
+        assert!(validate_all("const x = 1;", &config).is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! Finding `await` used outside an `async` function
+
+use super::lexer::{expression_end, is_arrow, match_brackets, tokenize, Token, TokenKind};
+use crate::{position, Diagnostic};
+
+/// Keywords whose parenthesized header opens a plain block, not a function
+const BLOCK_KEYWORDS: &[&str] = &["if", "for", "while", "switch", "catch", "with", "case"];
+
+/// Report every `await` that isn't inside an `async` function
+///
+/// An `await` at the top level of the contract, or in the body of a
+/// function, method or arrow function not marked `async`, produces an
+/// "Await Outside Async" diagnostic underlining the keyword, with help
+/// saying where `await` is allowed. Matches inside strings and comments are
+/// ignored, as are property accesses such as `job.await`. Nesting is
+/// followed through blocks, so an `await` inside an `if` in an `async`
+/// function passes, while one in a plain callback within it is reported.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::check_top_level_await;
+///
+/// let code = "const cfg = await load();\nexport async function handler() {\n  await save(cfg);\n}";
+/// let diagnostics = check_top_level_await(code);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!((diagnostics[0].span.line, diagnostics[0].span.col), (1, 13));
+/// ```
+pub fn check_top_level_await(code: &str) -> Vec<Diagnostic> {
+    let tokens = tokenize(code);
+    let matching = match_brackets(&tokens);
+
+    // Enclosing function bodies, innermost last, each with whether it is
+    // async and the index of the token it ends at
+    let mut functions: Vec<(bool, usize)> = Vec::new();
+    let mut diagnostics = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        while functions.last().is_some_and(|&(_, end)| end <= index) {
+            functions.pop();
+        }
+
+        match token.kind {
+            TokenKind::Punct('{') => {
+                if let Some(is_async) = function_body(code, &tokens, &matching, index) {
+                    functions.push((is_async, matching[index].unwrap_or(tokens.len())));
+                }
+            }
+            // An arrow function without braces ends with its expression
+            TokenKind::Punct('>')
+                if is_arrow(&tokens, index)
+                    && tokens.get(index + 1).map(|t| t.kind) != Some(TokenKind::Punct('{')) =>
+            {
+                let is_async = arrow_is_async(code, &tokens, &matching, index - 1);
+                functions.push((is_async, expression_end(&tokens, &matching, index + 1)));
+            }
+            TokenKind::Ident if token.text(code) == "await" => {
+                let after_dot = index > 0 && tokens[index - 1].kind == TokenKind::Punct('.');
+                let is_key = tokens.get(index + 1).map(|t| t.kind) == Some(TokenKind::Punct(':'));
+                let in_async = functions.last().is_some_and(|&(is_async, _)| is_async);
+                if after_dot || is_key || in_async {
+                    continue;
+                }
+
+                let (line, col) = position::offset_to_position(code, token.start);
+                diagnostics.push(
+                    Diagnostic::builder()
+                        .source(code)
+                        .kind("Await Outside Async")
+                        .line(line)
+                        .col(col)
+                        .underline("await".len())
+                        .message("'await' is only allowed inside an async function")
+                        .help(
+                            "await inside an `async function`, `async` method or `async` arrow \
+                             function, such as `export async function handler(ctx) { ... }`",
+                        )
+                        .build(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// Whether the `{` at `brace` opens a function body, and if so whether the
+/// function is async; `None` for blocks, classes and object literals
+fn function_body(
+    code: &str,
+    tokens: &[Token],
+    matching: &[Option<usize>],
+    brace: usize,
+) -> Option<bool> {
+    let before = brace.checked_sub(1)?;
+    if is_arrow(tokens, before) {
+        return Some(arrow_is_async(code, tokens, matching, before - 1));
+    }
+    let open = matching[params_close(tokens, before)?]?;
+
+    // `[async] function [*] [name] (` or a method `[async] [*] name (`
+    let mut prev = tokens[..open].iter().rev().peekable();
+    let head = **prev.peek()?;
+    match head.kind {
+        TokenKind::Ident if BLOCK_KEYWORDS.contains(&head.text(code)) => return None,
+        TokenKind::Ident if head.text(code) != "function" => {
+            prev.next();
+        }
+        TokenKind::Ident | TokenKind::Punct('*') => {}
+        _ => return None,
+    }
+    while prev
+        .next_if(|t| t.kind == TokenKind::Punct('*') || t.is_ident(code, "function"))
+        .is_some()
+    {}
+    Some(prev.next().is_some_and(|t| t.is_ident(code, "async")))
+}
+
+/// Whether the arrow function whose `=>` starts at `arrow` is async
+fn arrow_is_async(code: &str, tokens: &[Token], matching: &[Option<usize>], arrow: usize) -> bool {
+    let is_async = |index: Option<usize>| index.is_some_and(|k| tokens[k].is_ident(code, "async"));
+    let Some(last) = arrow.checked_sub(1) else {
+        return false;
+    };
+
+    // `async x => ...` or `async (x) => ...`
+    if tokens[last].kind == TokenKind::Ident {
+        return is_async(last.checked_sub(1));
+    }
+    params_close(tokens, last)
+        .and_then(|close| matching[close])
+        .is_some_and(|open| is_async(open.checked_sub(1)))
+}
+
+/// The `)` closing a parameter list that ends at token `last`, looking past
+/// a return type annotation such as `): Promise<void>`
+fn params_close(tokens: &[Token], last: usize) -> Option<usize> {
+    if tokens[last].kind == TokenKind::Punct(')') {
+        return Some(last);
+    }
+    (0..last)
+        .rev()
+        .take_while(|&k| !matches!(tokens[k].kind, TokenKind::Punct(';' | '{' | '}')))
+        .find(|&k| {
+            tokens[k].kind == TokenKind::Punct(')') && tokens[k + 1].kind == TokenKind::Punct(':')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flagged(code: &str) -> Vec<(usize, usize)> {
+        check_top_level_await(code)
+            .iter()
+            .map(|d| (d.span.line, d.span.col))
+            .collect()
+    }
+
+    #[test]
+    fn test_top_level_await_is_flagged() {
+        let code = "import { kv } from 'tana/kv';\nconst balance = await kv.get('balance');";
+        let diagnostics = check_top_level_await(code);
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (2, 17));
+        assert_eq!(diagnostic.span.length, 5);
+        assert_eq!(diagnostic.kind, "Await Outside Async");
+        assert!(diagnostic.render().contains("  2 │ const balance = await kv.get('balance');\n    │                 ^^^^^ 'await' is only allowed inside an async function\n"));
+    }
+
+    #[test]
+    fn test_await_inside_async_functions_passes() {
+        let code = "\
+export async function handler(ctx) {
+  if (ctx.ok) {
+    await save(ctx);
+  }
+}
+const run = async (x) => { await x; };
+const one = async x => await x;
+class Job {
+  async start(): Promise<void> {
+    for (const step of this.steps) {
+      await step();
+    }
+  }
+  async *[Symbol.asyncIterator]() {}
+}
+const gen = { async *items() { await next(); } };";
+
+        assert_eq!(flagged(code), []);
+    }
+
+    #[test]
+    fn test_await_in_strings_and_comments_is_ignored() {
+        let code = "const s = 'await me';\n// await later\n/* await */ const t = `await ${s}`;\njob.await;";
+
+        assert_eq!(flagged(code), []);
+    }
+
+    #[test]
+    fn test_await_inside_plain_function_is_flagged() {
+        let code = "\
+export async function handler(items) {
+  items.forEach(function (item) {
+    await save(item);
+  });
+  items.map(item => await save(item));
+  await done();
+}
+function sync() { await tick(); }";
+
+        assert_eq!(flagged(code), [(3, 5), (5, 21), (8, 19)]);
+    }
+
+    #[test]
+    fn test_await_in_template_substitution_and_after_regex_is_flagged() {
+        let code = "const s = `${await f()}`;\nconst re = /'/; await x;";
+
+        assert_eq!(flagged(code), [(1, 14), (2, 17)]);
+    }
+}
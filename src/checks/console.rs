@@ -0,0 +1,134 @@
+//! Finding `console` calls left over from Node.js code
+
+use super::lexer::{tokenize, Token, TokenKind};
+use crate::{position, Diagnostic, Severity};
+
+/// Warn about every `console.<method>(` call on the global console
+///
+/// Calls inside strings and comments are ignored, as are property accesses
+/// such as `logger.console.log(`. When the contract imports its own
+/// `console` (e.g. `import { console } from 'tana/core'`) the calls are
+/// legitimate and nothing is reported. Each call produces a "Console Usage"
+/// warning underlining `console`, with help pointing at the Tana logger.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{check_console_usage, Severity};
+///
+/// let code = "// console.log('debug');\nconsole.log(\"called\");";
+/// let diagnostics = check_console_usage(code);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].severity, Severity::Warning);
+/// assert_eq!((diagnostics[0].span.line, diagnostics[0].span.col), (2, 1));
+/// ```
+pub fn check_console_usage(code: &str) -> Vec<Diagnostic> {
+    let tokens = tokenize(code);
+    if imports_console(code, &tokens) {
+        return Vec::new();
+    }
+
+    let mut diagnostics = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let after_dot = index > 0 && tokens[index - 1].kind == TokenKind::Punct('.');
+        if !token.is_ident(code, "console") || after_dot {
+            continue;
+        }
+
+        // `console` `.` `method` `(`
+        let call = tokens.get(index + 1..index + 4);
+        let Some([dot, method, paren]) = call else {
+            continue;
+        };
+        if dot.kind != TokenKind::Punct('.')
+            || method.kind != TokenKind::Ident
+            || paren.kind != TokenKind::Punct('(')
+        {
+            continue;
+        }
+
+        let (line, col) = position::offset_to_position(code, token.start);
+        diagnostics.push(
+            Diagnostic::builder()
+                .source(code)
+                .kind("Console Usage")
+                .line(line)
+                .col(col)
+                .underline("console".len())
+                .message(format!(
+                    "console.{} output is discarded in contracts",
+                    method.text(code)
+                ))
+                .help("import { console } from 'tana/core' to log from a contract")
+                .severity(Severity::Warning)
+                .build(),
+        );
+    }
+
+    diagnostics
+}
+
+/// Whether an import statement binds the name `console`
+fn imports_console(code: &str, tokens: &[Token]) -> bool {
+    let mut in_import = false;
+    for (index, token) in tokens.iter().enumerate() {
+        let after_dot = index > 0 && tokens[index - 1].kind == TokenKind::Punct('.');
+        if token.is_ident(code, "import") && !after_dot {
+            in_import = true;
+        } else if token.kind == TokenKind::Str || token.kind == TokenKind::Punct(';') {
+            in_import = false;
+        } else if in_import && token.is_ident(code, "console") {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_call_is_a_warning() {
+        let code = "export function run() {\n  console.log('starting');\n}";
+        let diagnostics = check_console_usage(code);
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (2, 3));
+        assert_eq!(diagnostic.span.length, 7);
+        assert_eq!(
+            diagnostic.message,
+            "console.log output is discarded in contracts"
+        );
+        assert!(diagnostic
+            .render()
+            .contains("  2 │   console.log('starting');\n    │   ~~~~~~~ console.log"));
+    }
+
+    #[test]
+    fn test_commented_out_call_is_ignored() {
+        let code = "// console.log('a');\n/* console.error(b); */\nlet x = 1;";
+
+        assert!(check_console_usage(code).is_empty());
+    }
+
+    #[test]
+    fn test_call_inside_string_is_ignored() {
+        let code = "const help = \"use console.log(x) to debug\";\nconst t = `console.warn(${x})`;";
+
+        assert!(check_console_usage(code).is_empty());
+    }
+
+    #[test]
+    fn test_imported_console_is_allowed() {
+        let code = "import { console } from 'tana/core';\nconsole.log('ok');";
+
+        assert!(check_console_usage(code).is_empty());
+
+        // Properties named `console`, and `console` not being called, pass too
+        assert!(check_console_usage("logger.console.log(1); console.table").is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Checking that a contract exports the entry point the runtime calls
+
+use super::lexer::{tokenize, Token, TokenKind};
+use crate::{position, Diagnostic, ErrorKind};
+
+/// Report a missing `expected` export, the entry point the runtime calls
+///
+/// `expected` is either `"default"`, satisfied by `export default`, or the
+/// name of a binding such as `"handler"`, satisfied by `export function
+/// handler`, `export const handler`, and the like, or by listing it in
+/// `export { ... }`. Exports inside strings and comments don't count. When
+/// the export is missing, the "Missing Export" diagnostic points at
+/// the end of the file, with help showing the export to add. The diagnostic
+/// has no file path; set `file_path` before rendering if needed.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{check_entry_point, ErrorKind};
+///
+/// let code = "export function handler(ctx) {\n  return ctx;\n}";
+/// assert!(check_entry_point(code, "handler").is_none());
+///
+/// let diagnostic = check_entry_point("function handler() {}", "handler").unwrap();
+/// assert_eq!(diagnostic.error_kind(), ErrorKind::MissingExport);
+/// ```
+pub fn check_entry_point(code: &str, expected: &str) -> Option<Diagnostic> {
+    let tokens = tokenize(code);
+    let exported = tokens.iter().enumerate().any(|(index, token)| {
+        let after_dot = index > 0 && tokens[index - 1].kind == TokenKind::Punct('.');
+        token.is_ident(code, "export")
+            && !after_dot
+            && exports(code, &tokens[index + 1..], expected)
+    });
+    if exported {
+        return None;
+    }
+
+    let (line, col) = position::offset_to_position(code, code.trim_end().len());
+    let (message, help) = if expected == "default" {
+        (
+            "contract has no default export".to_string(),
+            "add `export default function (ctx) { ... }`".to_string(),
+        )
+    } else {
+        (
+            format!("contract has no '{}' export", expected),
+            format!("add `export function {}(ctx) {{ ... }}`", expected),
+        )
+    };
+
+    Some(
+        Diagnostic::builder()
+            .source(code)
+            .kind(ErrorKind::MissingExport)
+            .line(line)
+            .col(col)
+            .underline(0)
+            .message(message)
+            .help(help)
+            .build(),
+    )
+}
+
+/// Whether the `export` statement starting at `rest` exports `expected`
+fn exports(code: &str, rest: &[Token], expected: &str) -> bool {
+    let Some(first) = rest.first() else {
+        return false;
+    };
+
+    if first.is_ident(code, "default") {
+        return expected == "default";
+    }
+
+    if first.kind == TokenKind::Punct('{') {
+        // `export { a, b as c }` exports the name after `as`, if any
+        return rest[1..]
+            .iter()
+            .take_while(|t| t.kind != TokenKind::Punct('}'))
+            .zip(rest[2..].iter().map(Some).chain([None]))
+            .any(|(token, next)| {
+                token.kind == TokenKind::Ident
+                    && token.text(code) == expected
+                    && !token.is_ident(code, "as")
+                    && !next.is_some_and(|next| next.is_ident(code, "as"))
+            });
+    }
+
+    // `export [async] function [*] name`, `export const name`, ...
+    let keyword = |t: &Token| {
+        ["async", "function", "const", "let", "var", "class"]
+            .iter()
+            .any(|keyword| t.is_ident(code, keyword))
+            || t.kind == TokenKind::Punct('*')
+    };
+    rest.iter()
+        .find(|t| !keyword(t))
+        .is_some_and(|name| name.is_ident(code, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_export_form_is_found() {
+        let forms = [
+            ("export default function (ctx) {}", "default"),
+            ("export default { run };", "default"),
+            ("export const handler = (ctx) => ctx;", "handler"),
+            ("export function handler(ctx) {}", "handler"),
+            ("export async function handler(ctx) {}", "handler"),
+            ("function run() {}\nexport { run as handler };", "handler"),
+            ("const handler = 1;\nexport { other, handler };", "handler"),
+        ];
+
+        for (code, expected) in forms {
+            assert!(check_entry_point(code, expected).is_none(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_missing_export_points_at_end_of_file() {
+        let code = "import { kv } from 'tana/kv';\nfunction handler(ctx) {\n  return ctx;\n}\n";
+        let diagnostic = check_entry_point(code, "handler").unwrap();
+
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (4, 2));
+        assert_eq!(diagnostic.kind, "Missing Export");
+        assert!(crate::explain(&diagnostic.error_kind()).is_some());
+        assert_eq!(diagnostic.message, "contract has no 'handler' export");
+        assert_eq!(
+            diagnostic.help.as_deref(),
+            Some("add `export function handler(ctx) { ... }`")
+        );
+    }
+
+    #[test]
+    fn test_near_misses_are_missing() {
+        let misses = [
+            "// export function handler() {}",
+            "const s = 'export default 1';",
+            "export function handlerV2() {}",
+            "export { handler as run };",
+            "export const other = 1;",
+        ];
+
+        for code in misses {
+            assert!(check_entry_point(code, "handler").is_some(), "{}", code);
+        }
+        assert!(check_entry_point("export function handler() {}", "default").is_some());
+    }
+
+    #[test]
+    fn test_empty_file_points_at_line_one() {
+        let diagnostic = check_entry_point("", "default").unwrap();
+
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (1, 1));
+        assert_eq!(diagnostic.message, "contract has no default export");
+    }
+}
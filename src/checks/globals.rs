@@ -0,0 +1,273 @@
+//! Finding references to browser and Node.js globals the sandbox lacks
+
+use std::ops::Range;
+
+use super::lexer::{expression_end, is_arrow, match_brackets, tokenize, Token, TokenKind};
+use crate::{position, text, Diagnostic, ErrorKind};
+
+/// Keywords whose parenthesized header is followed by a block but binds nothing
+const BLOCK_KEYWORDS: &[&str] = &["if", "for", "while", "switch", "with"];
+
+/// Report every bare reference to one of the `forbidden` globals
+///
+/// Only references to the global itself count: property accesses such as
+/// `config.window` or `obj?.process`, object keys such as `{ window: true }`,
+/// method names, and any text inside strings or comments are ignored. So
+/// are function parameters with a forbidden name and their uses in the
+/// function, which refer to the parameter. Code inside a template literal's
+/// `${...}` is checked like any other. Each reference produces a "Forbidden
+/// Global" diagnostic underlining the identifier, with help pointing at the
+/// sandbox-safe alternative. The diagnostics have no file path; set
+/// `file_path` before rendering if needed.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::check_forbidden_globals;
+///
+/// let code = "const env = process.env;\nconst name = 'process';";
+/// let diagnostics = check_forbidden_globals(code, &["window", "process"]);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!((diagnostics[0].span.line, diagnostics[0].span.col), (1, 13));
+/// ```
+pub fn check_forbidden_globals(code: &str, forbidden: &[&str]) -> Vec<Diagnostic> {
+    let tokens = tokenize(code);
+    let matching = match_brackets(&tokens);
+    let parameters = parameters(code, &tokens, &matching);
+    let mut diagnostics = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Ident {
+            continue;
+        }
+        let name = token.text(code);
+        let shadowed = parameters
+            .iter()
+            .any(|(param, scope)| *param == name && scope.contains(&index));
+        if !forbidden.contains(&name) || shadowed || !is_reference(&tokens, &matching, index) {
+            continue;
+        }
+
+        let (line, col) = position::offset_to_position(code, token.start);
+        diagnostics.push(
+            Diagnostic::builder()
+                .source(code)
+                .kind(ErrorKind::ForbiddenGlobal)
+                .line(line)
+                .col(col)
+                .underline(text::width(name))
+                .message(format!("'{}' is not available in contracts", name))
+                .help(alternative(name))
+                .build(),
+        );
+    }
+
+    diagnostics
+}
+
+/// Whether the identifier at `index` refers to a variable, rather than
+/// naming a property, object key or method
+fn is_reference(tokens: &[Token], matching: &[Option<usize>], index: usize) -> bool {
+    let kind = |index: usize| tokens.get(index).map(|t| t.kind);
+    let prev = index.checked_sub(1).and_then(kind);
+
+    // `a.window` and `a?.window` are properties, not the global
+    if prev == Some(TokenKind::Punct('.')) {
+        return false;
+    }
+    // `{ window: true }` and `{ a, process: 1 }` are keys
+    if kind(index + 1) == Some(TokenKind::Punct(':'))
+        && matches!(prev, Some(TokenKind::Punct('{' | ',')))
+    {
+        return false;
+    }
+    // `process() { ... }` defines a method
+    let after_params = match kind(index + 1) {
+        Some(TokenKind::Punct('(')) => matching[index + 1].and_then(|close| kind(close + 1)),
+        _ => None,
+    };
+    after_params != Some(TokenKind::Punct('{'))
+}
+
+/// Each function parameter's name and the tokens where it's in scope: its
+/// parameter list and the function body
+fn parameters<'a>(
+    code: &'a str,
+    tokens: &[Token],
+    matching: &[Option<usize>],
+) -> Vec<(&'a str, Range<usize>)> {
+    let mut parameters = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        // `x => ...`, with a single unparenthesized parameter
+        if token.kind == TokenKind::Ident && is_arrow(tokens, index + 2) {
+            let end = body_end(tokens, matching, index + 3);
+            parameters.push((token.text(code), index..end));
+            continue;
+        }
+
+        if token.kind != TokenKind::Punct('(') {
+            continue;
+        }
+        let Some(close) = matching[index] else {
+            continue;
+        };
+        let keyword = index.checked_sub(1).map(|k| tokens[k]);
+        if keyword.is_some_and(|k| BLOCK_KEYWORDS.iter().any(|word| k.is_ident(code, word))) {
+            continue;
+        }
+
+        // `(...) {`, `(...): Type {` or `(...) =>` starts a function body
+        let mut next = close + 1;
+        if tokens.get(next).map(|t| t.kind) == Some(TokenKind::Punct(':')) {
+            next = (next..tokens.len())
+                .take_while(|&k| !matches!(tokens[k].kind, TokenKind::Punct(';' | ')' | '}')))
+                .find(|&k| tokens[k].kind == TokenKind::Punct('{') || is_arrow(tokens, k + 1))
+                .unwrap_or(tokens.len());
+        }
+        let end = match tokens.get(next).map(|t| t.kind) {
+            Some(TokenKind::Punct('{')) => matching[next].unwrap_or(tokens.len()),
+            _ if is_arrow(tokens, next + 1) => body_end(tokens, matching, next + 2),
+            _ => continue,
+        };
+
+        // A binding follows `(`, `,`, `[`, `{` or the `...` of a rest
+        // parameter; other names are defaults, types or keys
+        for param in index + 1..close {
+            let binds = matches!(
+                tokens[param - 1].kind,
+                TokenKind::Punct('(' | ',' | '[' | '{' | '.')
+            );
+            if tokens[param].kind == TokenKind::Ident && binds {
+                parameters.push((tokens[param].text(code), index..end));
+            }
+        }
+    }
+    parameters
+}
+
+/// Index of the token ending an arrow function body starting at `start`
+fn body_end(tokens: &[Token], matching: &[Option<usize>], start: usize) -> usize {
+    match tokens.get(start).map(|t| t.kind) {
+        Some(TokenKind::Punct('{')) => matching[start].unwrap_or(tokens.len()),
+        _ => expression_end(tokens, matching, start),
+    }
+}
+
+/// Help describing what to use instead of the global `name`
+fn alternative(name: &str) -> String {
+    match name {
+        "console" => "import { console } from 'tana/core'".to_string(),
+        "window" | "document" | "globalThis" | "self" => {
+            "contracts have no browser globals; import what you need from 'tana/core'".to_string()
+        }
+        "localStorage" | "sessionStorage" => {
+            "persist contract state with 'tana/kv' instead".to_string()
+        }
+        "process" | "require" | "Buffer" | "global" => {
+            "contracts have no Node.js globals; import what you need from 'tana/core'".to_string()
+        }
+        "fetch" | "XMLHttpRequest" | "WebSocket" => {
+            "contracts cannot make network requests".to_string()
+        }
+        "setTimeout" | "setInterval" => {
+            "contracts run to completion and cannot schedule work".to_string()
+        }
+        _ => format!("'{}' does not exist in the Tana sandbox", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORBIDDEN: &[&str] = &["window", "process"];
+
+    #[test]
+    fn test_property_access_is_not_a_global() {
+        let code = "const w = config.window;\nconst p = opts?.process;";
+
+        assert!(check_forbidden_globals(code, FORBIDDEN).is_empty());
+    }
+
+    #[test]
+    fn test_strings_and_comments_are_ignored() {
+        let code = "const s = \"window\"; // process\nconst t = `no ${'window'} here`;";
+
+        assert!(check_forbidden_globals(code, FORBIDDEN).is_empty());
+    }
+
+    #[test]
+    fn test_bare_process_reference() {
+        let code = "export function run() {\n  return process.env.KEY;\n}";
+        let diagnostics = check_forbidden_globals(code, FORBIDDEN);
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.kind, "Forbidden Global");
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (2, 10));
+        assert_eq!(diagnostic.span.length, 7);
+        assert_eq!(
+            diagnostic.message,
+            "'process' is not available in contracts"
+        );
+        assert_eq!(
+            diagnostic.help.as_deref(),
+            Some("contracts have no Node.js globals; import what you need from 'tana/core'")
+        );
+    }
+
+    #[test]
+    fn test_window_foo_flags_window_only() {
+        let diagnostics = check_forbidden_globals("window.foo = 1;", &["window", "foo"]);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            (diagnostics[0].span.col, diagnostics[0].span.length),
+            (1, 6)
+        );
+    }
+
+    #[test]
+    fn test_object_keys_and_method_names_are_not_globals() {
+        let code = "const flags = { window: true, a: 1, process: 2 };
+class A {
+  process() {}
+}";
+
+        assert!(check_forbidden_globals(code, FORBIDDEN).is_empty());
+    }
+
+    #[test]
+    fn test_parameters_shadow_globals_in_their_function() {
+        let code = "\
+function f(process) { return process; }
+const g = (a, { window }) => window.open(a);
+const h = window => window;
+process.exit();";
+        let diagnostics = check_forbidden_globals(code, FORBIDDEN);
+
+        let found: Vec<(usize, usize)> = diagnostics
+            .iter()
+            .map(|d| (d.span.line, d.span.col))
+            .collect();
+        assert_eq!(found, [(4, 1)]);
+    }
+
+    #[test]
+    fn test_references_inside_template_substitutions() {
+        let code = "const url = `${window.location}/${'window'}`;";
+        let diagnostics = check_forbidden_globals(code, FORBIDDEN);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!((diagnostics[0].span.line, diagnostics[0].span.col), (1, 16));
+    }
+
+    #[test]
+    fn test_wide_global_is_underlined_in_display_columns() {
+        let diagnostics = check_forbidden_globals("名前();", &["名前"]);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span.length, 4);
+    }
+}
@@ -0,0 +1,123 @@
+//! Checking import specifiers against the modules a contract may use
+
+use super::lexer::{tokenize, TokenKind};
+use crate::{invalid_import, position, Diagnostic};
+
+/// Report every import whose module is not in `allowed`
+///
+/// Recognizes `import ... from 'module'` and bare `import 'module'`
+/// statements with either quote style, skipping dynamic `import()` calls
+/// and imports inside strings or comments. Each disallowed module produces
+/// an "Invalid Import" diagnostic underlining the specifier, with help
+/// suggesting the closest allowed module or listing them all. The
+/// diagnostics have no file path; set `file_path` before rendering if needed.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::validate_imports;
+///
+/// let code = "import { kv } from 'tana/kv';\nimport { tx } from 'tana/txs';";
+/// let diagnostics = validate_imports(code, &["tana/core", "tana/kv", "tana/tx"]);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!((diagnostics[0].span.line, diagnostics[0].span.col), (2, 21));
+/// assert_eq!(diagnostics[0].help.as_deref(), Some("did you mean 'tana/tx'?"));
+/// ```
+pub fn validate_imports(code: &str, allowed: &[&str]) -> Vec<Diagnostic> {
+    let tokens = tokenize(code);
+    let mut diagnostics = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        let after_dot = index > 0 && tokens[index - 1].kind == TokenKind::Punct('.');
+        if !token.is_ident(code, "import") || after_dot {
+            continue;
+        }
+
+        // The specifier is the first string, either right after `import` or
+        // after `from`, before the statement ends
+        let rest = &tokens[index + 1..];
+        let specifier = match rest.first() {
+            Some(next) if next.kind == TokenKind::Str => Some(next),
+            Some(next) if matches!(next.kind, TokenKind::Punct('(' | '.')) => None,
+            _ => rest
+                .iter()
+                .take_while(|t| t.kind != TokenKind::Punct(';'))
+                .zip(rest.iter().skip(1))
+                .find(|(before, t)| t.kind == TokenKind::Str && before.is_ident(code, "from"))
+                .map(|(_, t)| t),
+        };
+        let Some(specifier) = specifier else {
+            continue;
+        };
+
+        let (start, end) = specifier.string_contents();
+        let module = &code[start..end];
+        if !allowed.contains(&module) {
+            let (line, col) = position::offset_to_position(code, start);
+            diagnostics.push(invalid_import(code, "", line, col, module, allowed));
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED: &[&str] = &["tana/core", "tana/kv", "tana/block", "tana/tx"];
+
+    #[test]
+    fn test_valid_imports_pass() {
+        let code =
+            "import { kv } from 'tana/kv';\nimport \"tana/core\";\nconst m = await import('x');";
+
+        assert!(validate_imports(code, ALLOWED).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_import_points_at_specifier() {
+        let code = "import { console } from \"tana/invalid\";";
+        let diagnostics = validate_imports(code, ALLOWED);
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.kind, "Invalid Import");
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (1, 26));
+        assert_eq!(diagnostic.span.length, 12);
+        assert_eq!(diagnostic.message, "Module 'tana/invalid' not found");
+        assert!(diagnostic
+            .render()
+            .contains("    │                          ^^^^^^^^^^^^ Module"));
+    }
+
+    #[test]
+    fn test_two_invalid_imports_on_different_lines() {
+        let code = "import { a } from 'tana/kvs';\n// import { b } from 'tana/nope';\nimport {\n  from,\n} from 'lodash';";
+        let diagnostics = validate_imports(code, ALLOWED);
+
+        let found: Vec<(usize, usize, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.span.line, d.span.col, &*d.message))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, 20, "Module 'tana/kvs' not found"),
+                (5, 9, "Module 'lodash' not found"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_specifier_keeps_its_last_character() {
+        let diagnostics = validate_imports("import x from 'tana/kvs", ALLOWED);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Module 'tana/kvs' not found");
+
+        let diagnostics = validate_imports("import x from '名前", ALLOWED);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Module '名前' not found");
+    }
+}
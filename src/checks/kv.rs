@@ -0,0 +1,177 @@
+//! Checking literal `tana/kv` keys against the store's key rules
+
+use super::lexer::{tokenize, TokenKind};
+use crate::{position, text, Diagnostic};
+
+/// Longest key the store accepts, in characters
+const MAX_KEY_LENGTH: usize = 256;
+
+/// `kv` methods whose first argument is a key
+const KEYED_METHODS: &[&str] = &["get", "set", "put", "delete"];
+
+/// Help stating the rule every key must follow
+const KEY_RULE: &str = "kv keys are 1 to 256 characters of letters, digits, and _ - . : /";
+
+/// Report every literal `tana/kv` key the store would reject
+///
+/// Looks at calls such as `kv.get("...")` and `kv.set("...", value)` whose
+/// first argument is a string literal, and flags keys that are empty,
+/// longer than 256 characters, or contain characters other than ASCII
+/// letters, digits, `_`, `-`, `.`, `:` and `/`. Each diagnostic underlines
+/// the literal, quotes included, with help stating the rule. Keys built at
+/// runtime, such as a variable, a concatenation or a template with
+/// substitutions, can't be checked and are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::check_kv_keys;
+///
+/// let code = "kv.get('balance:alice');\nkv.set('last seen', now);";
+/// let diagnostics = check_kv_keys(code);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!((diagnostics[0].span.line, diagnostics[0].span.col), (2, 8));
+/// ```
+pub fn check_kv_keys(code: &str) -> Vec<Diagnostic> {
+    let tokens = tokenize(code);
+    let mut diagnostics = Vec::new();
+
+    for (index, token) in tokens.iter().enumerate() {
+        let after_dot = index > 0 && tokens[index - 1].kind == TokenKind::Punct('.');
+        if !token.is_ident(code, "kv") || after_dot {
+            continue;
+        }
+
+        // `kv` `.` `method` `(` `"key"` followed by `,` or `)`
+        let call = tokens.get(index + 1..index + 6);
+        let Some([dot, method, paren, key, after]) = call else {
+            continue;
+        };
+        if dot.kind != TokenKind::Punct('.')
+            || method.kind != TokenKind::Ident
+            || !KEYED_METHODS.contains(&method.text(code))
+            || paren.kind != TokenKind::Punct('(')
+            || key.kind != TokenKind::Str
+            || !matches!(after.kind, TokenKind::Punct(',' | ')'))
+        {
+            continue;
+        }
+        let literal = key.text(code);
+        if literal.starts_with('`') && literal.contains("${") {
+            continue;
+        }
+
+        let (start, end) = key.string_contents();
+        let Some(message) = key_problem(&code[start..end]) else {
+            continue;
+        };
+        let (line, col) = position::offset_to_position(code, key.start);
+        diagnostics.push(
+            Diagnostic::builder()
+                .source(code)
+                .kind("Invalid KV Key")
+                .line(line)
+                .col(col)
+                .underline(text::width(literal))
+                .message(message)
+                .help(KEY_RULE)
+                .build(),
+        );
+    }
+
+    diagnostics
+}
+
+/// What makes `key` unusable, or `None` if the store accepts it
+fn key_problem(key: &str) -> Option<String> {
+    let length = key.chars().count();
+    if length == 0 {
+        return Some("kv key is empty".to_string());
+    }
+    if length > MAX_KEY_LENGTH {
+        return Some(format!(
+            "kv key is {} characters long, over the {} limit",
+            length, MAX_KEY_LENGTH
+        ));
+    }
+    key.chars()
+        .find(|&ch| !(ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.' | ':' | '/')))
+        .map(|ch| format!("kv key '{}' contains {:?}, which keys can't use", key, ch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_keys_pass() {
+        let code =
+            "kv.get('balance:alice');\nkv.set(\"users/42.name\", name);\nkv.delete(`a_b-c`);";
+
+        assert!(check_kv_keys(code).is_empty());
+    }
+
+    #[test]
+    fn test_over_long_key_is_underlined() {
+        let key = "k".repeat(MAX_KEY_LENGTH + 1);
+        let code = format!("const v = kv.get('{}');", key);
+        let diagnostics = check_kv_keys(&code);
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (1, 18));
+        assert_eq!(diagnostic.span.length, MAX_KEY_LENGTH + 3);
+        assert_eq!(
+            diagnostic.message,
+            "kv key is 257 characters long, over the 256 limit"
+        );
+        assert_eq!(diagnostic.help.as_deref(), Some(KEY_RULE));
+    }
+
+    #[test]
+    fn test_illegal_character_and_empty_key() {
+        let diagnostics = check_kv_keys("kv.set('a b', 1);\nkv.get(\"\");");
+
+        let messages: Vec<&str> = diagnostics.iter().map(|d| &*d.message).collect();
+        assert_eq!(
+            messages,
+            [
+                "kv key 'a b' contains ' ', which keys can't use",
+                "kv key is empty"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dynamic_keys_are_skipped() {
+        let code =
+            "kv.get(key);\nkv.set('user:' + id, 1);\nkv.get(`user ${id}`);\nstore.kv.get('a b');";
+
+        assert!(check_kv_keys(code).is_empty());
+    }
+
+    #[test]
+    fn test_unterminated_key_is_checked_whole() {
+        let diagnostics = check_kv_keys("kv.get('名前\n);");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "kv key '名前' contains '名', which keys can't use"
+        );
+        // `'名前` is five display columns wide
+        assert_eq!(diagnostics[0].span.length, 5);
+    }
+
+    #[test]
+    fn test_wide_key_is_underlined_in_display_columns() {
+        let diagnostics = check_kv_keys("kv.get('名前');");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span.length, 6);
+        assert!(diagnostics[0]
+            .render()
+            .contains("  1 │ kv.get('名前');\n    │        ^^^^^^ "));
+    }
+}
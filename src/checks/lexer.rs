@@ -0,0 +1,385 @@
+//! A small JavaScript/TypeScript tokenizer for the source checks
+//!
+//! It only distinguishes what the checks need: identifiers, string and
+//! regular expression literals, and single punctuation characters. Comments
+//! and whitespace are skipped. Template literals are split around their `${...}`
+//! substitutions, so the literal text is never mistaken for code while the
+//! code inside a substitution is tokenized like any other.
+
+/// What a token is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// An identifier or keyword
+    Ident,
+    /// A quoted string or a piece of a template literal, quotes included
+    ///
+    /// A template with substitutions is split into pieces around them, such
+    /// as `` `a ${`` and `` } b` `` for `` `a ${x} b` ``.
+    Str,
+    /// A number literal
+    Number,
+    /// A regular expression literal, slashes and flags included
+    Regex,
+    /// Any other single character, such as `.` or `;`
+    Punct(char),
+}
+
+/// A token and the byte range it covers in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+    /// Whether a string has its closing quote; always true for other tokens
+    pub terminated: bool,
+}
+
+impl Token {
+    /// The source text of the token
+    pub(crate) fn text<'a>(&self, code: &'a str) -> &'a str {
+        &code[self.start..self.end]
+    }
+
+    /// Whether this is the identifier or keyword `word`
+    pub(crate) fn is_ident(&self, code: &str, word: &str) -> bool {
+        self.kind == TokenKind::Ident && self.text(code) == word
+    }
+
+    /// Byte range of a string token's contents, without its quotes
+    ///
+    /// An unterminated string's contents run to the end of the token.
+    pub(crate) fn string_contents(&self) -> (usize, usize) {
+        let end = if self.terminated {
+            self.end - 1
+        } else {
+            self.end
+        };
+        (self.start + 1, end.max(self.start + 1))
+    }
+}
+
+/// Keywords after which a `/` starts a regular expression, not a division
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+/// Split `code` into tokens, skipping whitespace and comments
+pub(crate) fn tokenize(code: &str) -> Vec<Token> {
+    let bytes = code.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    // Brace depth within each template substitution being read, innermost last
+    let mut substitutions: Vec<usize> = Vec::new();
+
+    while let Some(ch) = code[pos..].chars().next() {
+        let start = pos;
+        let mut terminated = true;
+        let kind = match ch {
+            c if c.is_whitespace() => {
+                pos += c.len_utf8();
+                continue;
+            }
+            '/' if bytes.get(pos + 1) == Some(&b'/') => {
+                pos = code[pos..]
+                    .find('\n')
+                    .map_or(code.len(), |offset| pos + offset);
+                continue;
+            }
+            '/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos = code[pos + 2..]
+                    .find("*/")
+                    .map_or(code.len(), |offset| pos + 2 + offset + 2);
+                continue;
+            }
+            '/' if regex_allowed(code, tokens.last()) && regex_end(code, pos).is_some() => {
+                pos = regex_end(code, pos).unwrap_or(code.len());
+                TokenKind::Regex
+            }
+            '"' | '\'' => {
+                (pos, terminated) = string_end(code, pos, ch);
+                TokenKind::Str
+            }
+            // A template, or the rest of one after a substitution's `}`
+            '`' | '}' if ch == '`' || substitutions.last() == Some(&0) => {
+                if ch == '}' {
+                    substitutions.pop();
+                }
+                (pos, terminated) = string_end(code, pos, '`');
+                if terminated && code[..pos].ends_with("${") {
+                    substitutions.push(0);
+                }
+                TokenKind::Str
+            }
+            c if is_ident_start(c) => {
+                pos += code[pos..]
+                    .find(|c: char| !is_ident_continue(c))
+                    .unwrap_or(code.len() - pos);
+                TokenKind::Ident
+            }
+            c if c.is_ascii_digit() => {
+                pos += code[pos..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                    .unwrap_or(code.len() - pos);
+                TokenKind::Number
+            }
+            c => {
+                match (c, substitutions.last_mut()) {
+                    ('{', Some(depth)) => *depth += 1,
+                    ('}', Some(depth)) => *depth -= 1,
+                    _ => {}
+                }
+                pos += c.len_utf8();
+                TokenKind::Punct(c)
+            }
+        };
+        tokens.push(Token {
+            kind,
+            start,
+            end: pos,
+            terminated,
+        });
+    }
+
+    tokens
+}
+
+/// Byte offset just past the string opened by `quote` at `start`, and
+/// whether the string was closed
+///
+/// Backslash escapes are honored. Quoted strings end at a newline if left
+/// unterminated; template literals may span lines, and a piece of one also
+/// ends after the `${` opening a substitution.
+fn string_end(code: &str, start: usize, quote: char) -> (usize, bool) {
+    let mut chars = code[start + 1..].char_indices().peekable();
+    while let Some((offset, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            '$' if quote == '`' && chars.next_if(|&(_, c)| c == '{').is_some() => {
+                return (start + 1 + offset + 2, true);
+            }
+            '\n' if quote != '`' => return (start + 1 + offset, false),
+            c if c == quote => return (start + 1 + offset + 1, true),
+            _ => {}
+        }
+    }
+    (code.len(), false)
+}
+
+/// Whether a `/` following `prev` starts a regular expression: at the start
+/// of an expression, rather than after an operand
+fn regex_allowed(code: &str, prev: Option<&Token>) -> bool {
+    let Some(prev) = prev else {
+        return true;
+    };
+    match prev.kind {
+        TokenKind::Punct(')' | ']' | '}') | TokenKind::Str | TokenKind::Number => false,
+        TokenKind::Regex => false,
+        TokenKind::Ident => EXPRESSION_KEYWORDS.contains(&prev.text(code)),
+        TokenKind::Punct(_) => true,
+    }
+}
+
+/// Byte offset just past the regular expression literal starting at `start`,
+/// flags included; `None` if it isn't closed on its line
+///
+/// A `/` inside a character class such as `[/]` doesn't close it.
+fn regex_end(code: &str, start: usize) -> Option<usize> {
+    let mut chars = code[start + 1..].char_indices();
+    let mut in_class = false;
+    while let Some((offset, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            '\n' => return None,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => {
+                let end = start + 1 + offset + 1;
+                let flags = code[end..]
+                    .find(|c: char| !is_ident_continue(c))
+                    .unwrap_or(code.len() - end);
+                return Some(end + flags);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// For every bracket token, the index of the bracket it pairs with
+pub(crate) fn match_brackets(tokens: &[Token]) -> Vec<Option<usize>> {
+    let mut matching = vec![None; tokens.len()];
+    let mut open = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let opener = match token.kind {
+            TokenKind::Punct('(' | '[' | '{') => {
+                open.push(index);
+                continue;
+            }
+            TokenKind::Punct(')') => '(',
+            TokenKind::Punct(']') => '[',
+            TokenKind::Punct('}') => '{',
+            _ => continue,
+        };
+        if let Some(&start) = open.last() {
+            if tokens[start].kind == TokenKind::Punct(opener) {
+                open.pop();
+                matching[start] = Some(index);
+                matching[index] = Some(start);
+            }
+        }
+    }
+    matching
+}
+
+/// Whether the token at `index` is the `>` of an `=>`
+pub(crate) fn is_arrow(tokens: &[Token], index: usize) -> bool {
+    index > 0
+        && index < tokens.len()
+        && tokens[index].kind == TokenKind::Punct('>')
+        && tokens[index - 1].kind == TokenKind::Punct('=')
+        && tokens[index - 1].end == tokens[index].start
+}
+
+/// Index of the token ending the expression starting at `start`: the first
+/// `,`, `;` or unmatched closing bracket outside nested brackets
+pub(crate) fn expression_end(tokens: &[Token], matching: &[Option<usize>], start: usize) -> usize {
+    let mut index = start;
+    while let Some(token) = tokens.get(index) {
+        match token.kind {
+            TokenKind::Punct('(' | '[' | '{') => match matching[index] {
+                Some(close) => index = close,
+                None => return tokens.len(),
+            },
+            TokenKind::Punct(',' | ';' | ')' | ']' | '}') => return index,
+            _ => {}
+        }
+        index += 1;
+    }
+    tokens.len()
+}
+
+fn is_ident_start(ch: char) -> bool {
+    ch == '_' || ch == '$' || ch.is_alphabetic()
+}
+
+fn is_ident_continue(ch: char) -> bool {
+    is_ident_start(ch) || ch.is_alphanumeric()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(code: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(code)
+            .iter()
+            .map(|token| (token.kind, token.text(code)))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_skips_comments_and_keeps_strings_whole() {
+        let code = "a.b // window\n/* process */ \"it's\" 'say \\'hi\\'' `x \\${y}` 42;";
+
+        assert_eq!(
+            kinds(code),
+            [
+                (TokenKind::Ident, "a"),
+                (TokenKind::Punct('.'), "."),
+                (TokenKind::Ident, "b"),
+                (TokenKind::Str, "\"it's\""),
+                (TokenKind::Str, "'say \\'hi\\''"),
+                (TokenKind::Str, "`x \\${y}`"),
+                (TokenKind::Number, "42"),
+                (TokenKind::Punct(';'), ";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_template_substitutions_as_code() {
+        let code = "`a ${ {k: `b ${c}`} } d` + e";
+
+        assert_eq!(
+            kinds(code),
+            [
+                (TokenKind::Str, "`a ${"),
+                (TokenKind::Punct('{'), "{"),
+                (TokenKind::Ident, "k"),
+                (TokenKind::Punct(':'), ":"),
+                (TokenKind::Str, "`b ${"),
+                (TokenKind::Ident, "c"),
+                (TokenKind::Str, "}`"),
+                (TokenKind::Punct('}'), "}"),
+                (TokenKind::Str, "} d`"),
+                (TokenKind::Punct('+'), "+"),
+                (TokenKind::Ident, "e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_regex_literals_whole() {
+        let code = "const re = /['`]\\//g; a / b / c; return /x/";
+
+        assert_eq!(
+            kinds(code),
+            [
+                (TokenKind::Ident, "const"),
+                (TokenKind::Ident, "re"),
+                (TokenKind::Punct('='), "="),
+                (TokenKind::Regex, "/['`]\\//g"),
+                (TokenKind::Punct(';'), ";"),
+                (TokenKind::Ident, "a"),
+                (TokenKind::Punct('/'), "/"),
+                (TokenKind::Ident, "b"),
+                (TokenKind::Punct('/'), "/"),
+                (TokenKind::Ident, "c"),
+                (TokenKind::Punct(';'), ";"),
+                (TokenKind::Ident, "return"),
+                (TokenKind::Regex, "/x/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_input() {
+        assert_eq!(
+            kinds("'open\nnext"),
+            [(TokenKind::Str, "'open"), (TokenKind::Ident, "next")]
+        );
+        assert_eq!(kinds("/* never closed"), []);
+
+        let open = tokenize("'名前\n")[0];
+        assert!(!open.terminated);
+        assert_eq!(open.string_contents(), (1, 7));
+        let closed = tokenize("'名前'")[0];
+        assert!(closed.terminated);
+        assert_eq!(closed.string_contents(), (1, 7));
+        assert_eq!(
+            kinds("名前 = 1"),
+            [
+                (TokenKind::Ident, "名前"),
+                (TokenKind::Punct('='), "="),
+                (TokenKind::Number, "1"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Source checks that find common contract mistakes
+//!
+//! Each check scans a contract's source and returns a [`Diagnostic`] for
+//! every problem it finds, ready to render.
+//!
+//! [`Diagnostic`]: crate::Diagnostic
+
+mod all;
+mod awaits;
+mod console;
+mod entry;
+mod globals;
+mod imports;
+mod kv;
+mod lexer;
+
+pub use all::{validate_all, ValidationConfig};
+pub use awaits::check_top_level_await;
+pub use console::check_console_usage;
+pub use entry::check_entry_point;
+pub use globals::check_forbidden_globals;
+pub use imports::validate_imports;
+pub use kv::check_kv_keys;
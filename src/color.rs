@@ -0,0 +1,312 @@
+//! ANSI escape sequences for colored terminal output
+
+use std::fmt;
+
+/// When the renderer should emit ANSI color escapes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum ColorChoice {
+    /// Always emit color, even when `NO_COLOR` is set
+    Always,
+    /// Never emit color
+    #[default]
+    Never,
+    /// Emit color when stdout is a terminal and `NO_COLOR` isn't set
+    Auto,
+}
+
+impl ColorChoice {
+    /// Decide whether color should be emitted for this choice
+    ///
+    /// `Auto` emits color only when stdout is a terminal, so output piped to
+    /// a file or another process stays plain. It also honors the
+    /// [`NO_COLOR`](https://no-color.org) convention: when the variable is set
+    /// to any value, output stays plain. There is no terminal on `wasm32`, so
+    /// `Auto` never emits color there.
+    pub fn should_color(self) -> bool {
+        self.should_color_on(stdout_is_terminal())
+    }
+
+    /// Like [`should_color`](Self::should_color), for output that does or
+    /// doesn't go to a terminal
+    ///
+    /// ```rust
+    /// use tana_validation::ColorChoice;
+    ///
+    /// assert!(!ColorChoice::Auto.should_color_on(false));
+    /// assert!(ColorChoice::Always.should_color_on(false));
+    /// ```
+    pub fn should_color_on(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => is_terminal && !no_color_requested(),
+        }
+    }
+}
+
+/// How many colors the terminal can display
+///
+/// Richer depths give each style a more distinct shade, such as orange for
+/// warnings, that the basic palette can't express. Only the color escapes
+/// change; output without color is identical at every depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorDepth {
+    /// The 16 basic ANSI colors, understood by every color terminal
+    #[default]
+    Ansi16,
+    /// The xterm 256-color palette (`\x1b[38;5;Nm`)
+    Ansi256,
+    /// 24-bit RGB colors (`\x1b[38;2;R;G;Bm`)
+    TrueColor,
+}
+
+/// Whether the user asked for plain output through `NO_COLOR`
+#[cfg(not(target_arch = "wasm32"))]
+fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn no_color_requested() -> bool {
+    false
+}
+
+/// Whether stdout is attached to a terminal
+#[cfg(not(target_arch = "wasm32"))]
+fn stdout_is_terminal() -> bool {
+    use std::io::IsTerminal;
+
+    std::io::stdout().is_terminal()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn stdout_is_terminal() -> bool {
+    false
+}
+
+/// Reset all attributes
+const RESET: &str = "\x1b[0m";
+
+/// Styles used by the colored renderer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    Red,
+    BrightRed,
+    Green,
+    Yellow,
+    BrightYellow,
+    Blue,
+    BrightBlue,
+    Cyan,
+    BrightCyan,
+    Dim,
+}
+
+impl Style {
+    fn escape(self) -> &'static str {
+        match self {
+            Style::Red => "\x1b[31m",
+            Style::BrightRed => "\x1b[91m",
+            Style::Green => "\x1b[32m",
+            Style::Yellow => "\x1b[33m",
+            Style::BrightYellow => "\x1b[93m",
+            Style::Blue => "\x1b[34m",
+            Style::BrightBlue => "\x1b[94m",
+            Style::Cyan => "\x1b[36m",
+            Style::BrightCyan => "\x1b[96m",
+            Style::Dim => "\x1b[2m",
+        }
+    }
+
+    /// The style's xterm 256-color index and RGB value; `None` for
+    /// attributes like dim that aren't colors
+    fn palette(self) -> Option<(u8, [u8; 3])> {
+        match self {
+            Style::Red => Some((160, [215, 0, 0])),
+            Style::BrightRed => Some((196, [255, 0, 0])),
+            Style::Green => Some((34, [0, 175, 0])),
+            Style::Yellow => Some((208, [255, 135, 0])),
+            Style::BrightYellow => Some((214, [255, 175, 0])),
+            Style::Blue => Some((33, [0, 135, 255])),
+            Style::BrightBlue => Some((75, [95, 175, 255])),
+            Style::Cyan => Some((37, [0, 175, 175])),
+            Style::BrightCyan => Some((51, [0, 255, 255])),
+            Style::Dim => None,
+        }
+    }
+
+    /// Write the escape that starts this style at `depth`
+    fn write_escape(self, f: &mut fmt::Formatter<'_>, depth: ColorDepth) -> fmt::Result {
+        match (depth, self.palette()) {
+            (ColorDepth::Ansi256, Some((index, _))) => write!(f, "\x1b[38;5;{}m", index),
+            (ColorDepth::TrueColor, Some((_, [r, g, b]))) => {
+                write!(f, "\x1b[38;2;{};{};{}m", r, g, b)
+            }
+            _ => f.write_str(self.escape()),
+        }
+    }
+}
+
+/// Applies styles when color is enabled and passes text through otherwise
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Painter {
+    enabled: bool,
+    depth: ColorDepth,
+}
+
+impl Painter {
+    pub(crate) fn new(enabled: bool, depth: ColorDepth) -> Self {
+        Self { enabled, depth }
+    }
+
+    /// Wrap `text` in the escape for `style`, followed by a reset
+    pub(crate) fn paint<'a>(&self, style: Style, text: &'a str) -> Painted<'a> {
+        Painted {
+            style: (self.enabled && !text.is_empty()).then_some(style),
+            depth: self.depth,
+            text,
+        }
+    }
+}
+
+/// Text that displays with its style's escapes, written without allocating
+pub(crate) struct Painted<'a> {
+    style: Option<Style>,
+    depth: ColorDepth,
+    text: &'a str,
+}
+
+impl fmt::Display for Painted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Some(style) => {
+                style.write_escape(f, self.depth)?;
+                write!(f, "{}{}", self.text, RESET)
+            }
+            None => f.write_str(self.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Serializes tests that modify `NO_COLOR`
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_painter_wraps_only_when_enabled() {
+        let paint = |enabled, style, text| {
+            Painter::new(enabled, ColorDepth::Ansi16)
+                .paint(style, text)
+                .to_string()
+        };
+
+        assert_eq!(paint(true, Style::Red, "x"), "\x1b[31mx\x1b[0m");
+        assert_eq!(paint(false, Style::Red, "x"), "x");
+        assert_eq!(paint(true, Style::Dim, ""), "");
+    }
+
+    #[test]
+    fn test_depth_selects_escape() {
+        let paint = |depth, style| Painter::new(true, depth).paint(style, "x").to_string();
+
+        assert_eq!(paint(ColorDepth::Ansi16, Style::Yellow), "\x1b[33mx\x1b[0m");
+        assert_eq!(
+            paint(ColorDepth::Ansi256, Style::Yellow),
+            "\x1b[38;5;208mx\x1b[0m"
+        );
+        assert_eq!(
+            paint(ColorDepth::TrueColor, Style::Yellow),
+            "\x1b[38;2;255;135;0mx\x1b[0m"
+        );
+
+        // Attributes are the same at every depth, and disabled painting stays plain
+        assert_eq!(paint(ColorDepth::TrueColor, Style::Dim), "\x1b[2mx\x1b[0m");
+        let plain = Painter::new(false, ColorDepth::TrueColor).paint(Style::Red, "x");
+        assert_eq!(plain.to_string(), "x");
+    }
+
+    #[test]
+    fn test_explicit_choices_ignore_no_color() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        std::env::set_var("NO_COLOR", "1");
+
+        assert!(ColorChoice::Always.should_color());
+        assert!(!ColorChoice::Never.should_color());
+
+        std::env::remove_var("NO_COLOR");
+    }
+
+    #[test]
+    fn test_auto_honors_no_color() {
+        let _guard = ENV_LOCK.lock().unwrap();
+
+        std::env::set_var("NO_COLOR", "1");
+        assert!(!ColorChoice::Auto.should_color_on(true));
+
+        // Any value counts, including an empty one
+        std::env::set_var("NO_COLOR", "");
+        assert!(!ColorChoice::Auto.should_color_on(true));
+
+        std::env::remove_var("NO_COLOR");
+        assert!(ColorChoice::Auto.should_color_on(true));
+    }
+
+    #[test]
+    fn test_auto_is_plain_off_terminal() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        std::env::remove_var("NO_COLOR");
+
+        assert!(!ColorChoice::Auto.should_color_on(false));
+        assert!(ColorChoice::Always.should_color_on(false));
+        assert!(!ColorChoice::Never.should_color_on(true));
+    }
+
+    #[test]
+    fn test_colored_error_ignores_stdout_but_honors_no_color() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let format = || {
+            crate::format_validation_error_colored(
+                "import { x } from 'tana/nope';",
+                "contract.ts",
+                "Invalid Import",
+                1,
+                20,
+                "Module 'tana/nope' not found",
+                "check the name",
+                11,
+            )
+        };
+
+        // Colored even though test output isn't a terminal
+        std::env::remove_var("NO_COLOR");
+        let colored = format();
+        assert!(colored.contains("\x1b["));
+
+        std::env::set_var("NO_COLOR", "1");
+        let plain = format();
+        std::env::remove_var("NO_COLOR");
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(
+            plain,
+            crate::format_validation_error(
+                "import { x } from 'tana/nope';",
+                "contract.ts",
+                "Invalid Import",
+                1,
+                20,
+                "Module 'tana/nope' not found",
+                "check the name",
+                11,
+            )
+        );
+    }
+}
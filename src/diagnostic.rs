@@ -0,0 +1,1597 @@
+//! The `Diagnostic` type and its builder
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::{
+    position, render, text, BoxStyle, ColorChoice, FormatOptions, Hyperlinks, LabelPlacement,
+    PositionBase, Theme,
+};
+
+/// How serious a diagnostic is
+///
+/// Severity picks the icon and title of the rendered box. Variants are
+/// ordered from most to least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum Severity {
+    /// A problem that prevents the contract from running
+    #[default]
+    Error,
+    /// Something suspicious that doesn't block execution
+    Warning,
+    /// Neutral information about the contract
+    Info,
+    /// A suggestion for improving the contract
+    Hint,
+}
+
+impl Severity {
+    /// Every severity, from most to least severe
+    pub const ALL: [Severity; 4] = [
+        Severity::Error,
+        Severity::Warning,
+        Severity::Info,
+        Severity::Hint,
+    ];
+
+    /// Lowercase name used by the machine-readable outputs
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+            Severity::Hint => "hint",
+        }
+    }
+
+    /// Icon printed before the error kind
+    pub fn icon(self) -> &'static str {
+        match self {
+            Severity::Error => "❌",
+            Severity::Warning => "⚠️",
+            Severity::Info => "ℹ️",
+            Severity::Hint => "💡",
+        }
+    }
+
+    /// Title printed at the top of the rendered box
+    pub fn title(self) -> &'static str {
+        match self {
+            Severity::Error => "Validation Error",
+            Severity::Warning => "Validation Warning",
+            Severity::Info => "Validation Info",
+            Severity::Hint => "Validation Hint",
+        }
+    }
+}
+
+/// Category of a diagnostic, shown after the icon on its kind line
+///
+/// The well-known Tana categories each have a canonical title, so a
+/// misspelled kind can't slip past code that matches on it; anything else
+/// is [`ErrorKind::Other`]. A diagnostic stores its kind as text, and
+/// [`DiagnosticBuilder::kind`] accepts an `ErrorKind` directly.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, ErrorKind};
+///
+/// let diagnostic = Diagnostic::builder().kind(ErrorKind::InvalidImport).build();
+///
+/// assert_eq!(diagnostic.kind, "Invalid Import");
+/// assert_eq!(diagnostic.error_kind(), ErrorKind::InvalidImport);
+/// assert_eq!(ErrorKind::from("invalid_import"), ErrorKind::InvalidImport);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// An import of a module that doesn't exist
+    InvalidImport,
+    /// A value of the wrong type
+    TypeError,
+    /// Use of a global that contracts can't access
+    ForbiddenGlobal,
+    /// A required export is missing
+    MissingExport,
+    /// Source that doesn't parse
+    SyntaxError,
+    /// Any other category, shown as given
+    Other(String),
+}
+
+impl ErrorKind {
+    /// Every variant with a canonical title
+    pub(crate) const KNOWN: [ErrorKind; 5] = [
+        ErrorKind::InvalidImport,
+        ErrorKind::TypeError,
+        ErrorKind::ForbiddenGlobal,
+        ErrorKind::MissingExport,
+        ErrorKind::SyntaxError,
+    ];
+
+    /// Title printed on the kind line, such as `Invalid Import`
+    pub fn title(&self) -> &str {
+        match self {
+            ErrorKind::Other(kind) => kind,
+            known => known.canonical_title().unwrap_or_default(),
+        }
+    }
+
+    /// Title of a well-known variant; `None` for [`ErrorKind::Other`]
+    fn canonical_title(&self) -> Option<&'static str> {
+        match self {
+            ErrorKind::InvalidImport => Some("Invalid Import"),
+            ErrorKind::TypeError => Some("Type Error"),
+            ErrorKind::ForbiddenGlobal => Some("Forbidden Global"),
+            ErrorKind::MissingExport => Some("Missing Export"),
+            ErrorKind::SyntaxError => Some("Syntax Error"),
+            ErrorKind::Other(_) => None,
+        }
+    }
+}
+
+/// Matches a well-known kind ignoring case, spaces, `_` and `-`, so
+/// `"invalid_import"` and `"Invalid Import"` are both
+/// [`ErrorKind::InvalidImport`]; anything else is kept as
+/// [`ErrorKind::Other`]
+impl From<&str> for ErrorKind {
+    fn from(kind: &str) -> Self {
+        let key = |title: &str| -> String {
+            title
+                .chars()
+                .filter(|ch| !matches!(ch, ' ' | '_' | '-'))
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+        let wanted = key(kind);
+        ErrorKind::KNOWN
+            .into_iter()
+            .find(|known| {
+                known
+                    .canonical_title()
+                    .is_some_and(|title| key(title) == wanted)
+            })
+            .unwrap_or_else(|| ErrorKind::Other(kind.to_string()))
+    }
+}
+
+/// Well-known kinds borrow their canonical title; others keep their text
+impl From<ErrorKind> for Cow<'static, str> {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Other(kind) => Cow::Owned(kind),
+            known => Cow::Borrowed(known.canonical_title().unwrap_or_default()),
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.title())
+    }
+}
+
+/// A secondary location pointed at alongside the primary error
+///
+/// Secondary labels are underlined with `~~~` and carry their own message,
+/// such as where a conflicting type was declared.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Label {
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Column number (1-indexed, counted in characters)
+    pub col: usize,
+    /// Number of display columns to underline (for ~~~)
+    pub length: usize,
+    /// Message printed after the underline
+    pub message: String,
+}
+
+impl Label {
+    /// Create a label underlining `length` columns from `line:col`
+    pub fn new(line: usize, col: usize, length: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            col,
+            length,
+            message: message.into(),
+        }
+    }
+}
+
+/// A position elsewhere, often in another file, cited by a diagnostic
+///
+/// Related locations are listed after the help and note as
+/// `= related: types.ts:10:5: {note}` lines. Their source is never read or
+/// shown, so they can point into files the renderer doesn't have.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, RelatedLocation};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .message("expected number, found string")
+///     .related(RelatedLocation::new("types.ts", 10, 5, "Price defined here"))
+///     .build();
+///
+/// assert!(diagnostic.render().contains("= related: types.ts:10:5: Price defined here\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RelatedLocation {
+    /// Path to the file (e.g., "types.ts")
+    pub file_path: String,
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Column number (1-indexed, counted in characters)
+    pub col: usize,
+    /// What is at the location, printed after it
+    pub note: String,
+}
+
+impl RelatedLocation {
+    /// Cite `file_path:line:col` with `note` explaining its relevance
+    pub fn new(
+        file_path: impl Into<String>,
+        line: usize,
+        col: usize,
+        note: impl Into<String>,
+    ) -> Self {
+        Self {
+            file_path: file_path.into(),
+            line,
+            col,
+            note: note.into(),
+        }
+    }
+}
+
+/// The expected and found types of a type error
+///
+/// Rendered beneath the snippet as two aligned lines, `expected: Int` over
+/// `found: String`, with the expected type in green and the found type in
+/// red when color is on.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, ErrorKind, TypeMismatch};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .source("const total: number = '12';")
+///     .kind(ErrorKind::TypeError)
+///     .col(23)
+///     .underline(4)
+///     .message("mismatched types")
+///     .type_mismatch(TypeMismatch::new("number", "string"))
+///     .build();
+///
+/// let rendered = diagnostic.render();
+/// assert!(rendered.contains("    │ expected: number\n    │    found: string\n"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct TypeMismatch {
+    /// The type the code called for
+    pub expected: String,
+    /// The type the code actually had
+    pub found: String,
+}
+
+impl TypeMismatch {
+    /// Compare the `expected` type with the `found` one
+    pub fn new(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        Self {
+            expected: expected.into(),
+            found: found.into(),
+        }
+    }
+}
+
+/// Where in the source a diagnostic points
+///
+/// A span either underlines `length` display columns of its start line, as
+/// made by [`Span::single_line`], or runs up to an end position on the same
+/// or a later line, as made by [`Span::range`]. A range underlines its
+/// first line from the start column, its last line up to the end column,
+/// and every line between in full.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, Span};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .source("const a = {\n  b: 1,\n")
+///     .span(Span::range(1, 11, 2, 8))
+///     .message("unterminated object")
+///     .build();
+///
+/// assert!(diagnostic.render().contains("  2 │   b: 1,\n    │ ^^^^^^^ unterminated object\n"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Span {
+    /// Line the span starts on (1-indexed)
+    pub line: usize,
+    /// Column the span starts at (1-indexed, counted in characters)
+    pub col: usize,
+    /// Number of display columns to underline; unused when `end` is set
+    pub length: usize,
+    /// `(line, col)` the span runs up to, not including; `None` for a span
+    /// of `length` columns
+    pub end: Option<(usize, usize)>,
+}
+
+impl Span {
+    /// A span underlining `length` display columns from `line:col`
+    pub fn single_line(line: usize, col: usize, length: usize) -> Self {
+        Self {
+            line,
+            col,
+            length,
+            end: None,
+        }
+    }
+
+    /// A span from `start_line:start_col` up to, but not including,
+    /// `end_line:end_col`
+    ///
+    /// An end line before the start line is treated as the start line.
+    pub fn range(start_line: usize, start_col: usize, end_line: usize, end_col: usize) -> Self {
+        Self {
+            line: start_line,
+            col: start_col,
+            length: 0,
+            end: Some((end_line.max(start_line), end_col)),
+        }
+    }
+}
+
+/// A proposed fix replacing part of one source line
+///
+/// The span starts at `line:col` and covers `length` characters; rendering
+/// shows the line before and after `replacement` is spliced in.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Suggestion {
+    /// Line number (1-indexed)
+    pub line: usize,
+    /// Column number (1-indexed, counted in characters)
+    pub col: usize,
+    /// Number of characters replaced; 0 inserts at `col`
+    pub length: usize,
+    /// Text put in place of the span
+    pub replacement: String,
+}
+
+impl Suggestion {
+    /// Suggest replacing `length` characters from `line:col` with `replacement`
+    pub fn new(line: usize, col: usize, length: usize, replacement: impl Into<String>) -> Self {
+        Self {
+            line,
+            col,
+            length,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// `line` with the replacement applied, clamping the span to the line
+    pub(crate) fn apply_to_line(&self, line: &str) -> String {
+        let start = self.col.saturating_sub(1);
+        let mut fixed: String = line.chars().take(start).collect();
+        fixed.push_str(&self.replacement);
+        fixed.extend(line.chars().skip(start + self.length));
+        fixed
+    }
+}
+
+/// A single validation problem located in a contract's source
+///
+/// Construct one with [`Diagnostic::builder`] so every position and text
+/// field is named at the call site, then call [`Diagnostic::render`] to get
+/// the same output as [`format_validation_error`](crate::format_validation_error).
+///
+/// The kind, message and help are [`Cow`]s, so string literals are stored
+/// without copying while formatted text is stored as an owned `String`.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::Diagnostic;
+///
+/// let diagnostic = Diagnostic::builder()
+///     .source("import { console } from 'tana/invalid';")
+///     .file("contract.ts")
+///     .kind("Invalid Import")
+///     .line(1)
+///     .col(26)
+///     .underline(12)
+///     .message("Module 'tana/invalid' not found")
+///     .help("Available modules: tana/core, tana/kv")
+///     .build();
+///
+/// assert!(diagnostic.render().contains("contract.ts:1:26"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct Diagnostic {
+    /// The source code containing the error
+    pub source: String,
+    /// Path to the file (e.g., "contract.ts")
+    pub file_path: String,
+    /// Category of error (e.g., "Invalid Import", "Type Error")
+    pub kind: Cow<'static, str>,
+    /// Stable identifier for the failure class (e.g., "T0001"), shown as
+    /// `[T0001]` after the kind so users can look up its docs
+    pub code: Option<String>,
+    /// Where in the source the error is underlined; the builder clamps a
+    /// start column of 0 to 1
+    pub span: Span,
+    /// Error message
+    pub message: Cow<'static, str>,
+    /// Help text explaining how to fix; the `= help:` block is omitted when
+    /// this is `None` or empty
+    pub help: Option<Cow<'static, str>>,
+    /// Additional explanation printed as `= note:` after the help; omitted
+    /// when `None` or empty
+    pub note: Option<String>,
+    /// Page explaining this kind of error, printed as a final `= see {url}`
+    /// line after the help and note
+    pub docs_url: Option<String>,
+    /// Other locations related to the error, each with its own message
+    pub secondary_labels: Vec<Label>,
+    /// Positions cited as `= related:` lines after the help and note,
+    /// without showing their source
+    pub related: Vec<RelatedLocation>,
+    /// A mechanical fix, shown as the line before and after the change
+    pub suggestion: Option<Suggestion>,
+    /// Expected and found types, shown as two aligned lines beneath the
+    /// snippet
+    pub type_mismatch: Option<TypeMismatch>,
+    /// How serious the problem is; selects the icon and title
+    pub severity: Severity,
+    /// Presentation settings used by [`Diagnostic::render`]
+    pub options: FormatOptions,
+    /// Whether rendering emits ANSI color escapes
+    pub color: ColorChoice,
+}
+
+impl Default for Diagnostic {
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            file_path: String::new(),
+            kind: Cow::Borrowed(""),
+            code: None,
+            span: Span::single_line(1, 1, 1),
+            message: Cow::Borrowed(""),
+            help: None,
+            note: None,
+            docs_url: None,
+            secondary_labels: Vec::new(),
+            related: Vec::new(),
+            suggestion: None,
+            type_mismatch: None,
+            severity: Severity::Error,
+            options: FormatOptions::default(),
+            color: ColorChoice::Never,
+        }
+    }
+}
+
+/// Diagnostics order by source position: file path, then line, then column,
+/// then severity
+///
+/// File paths compare as strings, byte by byte, so `B.ts` sorts before
+/// `a.ts`. At the same position, more severe diagnostics come first, so an
+/// error sorts before a warning. Remaining ties are broken by the other
+/// fields, kind and message first, so only diagnostics that are `==`
+/// compare as equal.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, Severity};
+///
+/// let warning = Diagnostic::builder().file("a.ts").line(2).severity(Severity::Warning).build();
+/// let error = Diagnostic::builder().file("a.ts").line(2).build();
+/// let earlier = Diagnostic::builder().file("a.ts").line(1).severity(Severity::Hint).build();
+///
+/// let mut diagnostics = vec![warning.clone(), error.clone(), earlier.clone()];
+/// diagnostics.sort();
+/// assert_eq!(diagnostics, [earlier, error, warning]);
+/// ```
+impl Ord for Diagnostic {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.file_path, self.span.line, self.span.col)
+            .cmp(&(&other.file_path, other.span.line, other.span.col))
+            .then(self.severity.cmp(&other.severity))
+            .then_with(|| self.tiebreak().cmp(&other.tiebreak()))
+    }
+}
+
+impl PartialOrd for Diagnostic {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Diagnostic {
+    /// The fields `Ord` compares after the position and severity
+    fn tiebreak(&self) -> impl Ord + '_ {
+        (
+            (&self.kind, &self.message, &self.code),
+            (self.span.length, self.span.end),
+            (&self.help, &self.note, &self.docs_url),
+            (&self.secondary_labels, &self.related),
+            (&self.suggestion, &self.type_mismatch),
+            (&self.source, &self.options, self.color),
+        )
+    }
+
+    /// Start building a diagnostic with named setters
+    pub fn builder() -> DiagnosticBuilder {
+        DiagnosticBuilder::default()
+    }
+
+    /// A diagnostic marking an insertion point at `line:col`
+    ///
+    /// Points have a span `length` of 0, meaning "something belongs
+    /// here" (such as an expected token) rather than "this text is wrong".
+    /// They currently render as a single caret, like a one-column underline.
+    /// Fill in the remaining fields with struct update syntax.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tana_validation::Diagnostic;
+    ///
+    /// let diagnostic = Diagnostic {
+    ///     source: "let x = 1".to_string(),
+    ///     kind: "Syntax Error".into(),
+    ///     message: "expected `;`".into(),
+    ///     ..Diagnostic::point(1, 10)
+    /// };
+    ///
+    /// assert!(diagnostic.render().contains("          ^ expected `;`"));
+    /// ```
+    pub fn point(line: usize, col: usize) -> Self {
+        Self {
+            span: Span::single_line(line, col, 0),
+            ..Self::default()
+        }
+    }
+
+    /// Last line the diagnostic's span touches
+    pub(crate) fn last_line(&self) -> usize {
+        let span = self.span;
+        span.end.map_or(span.line, |(line, _)| line.max(span.line))
+    }
+
+    /// Exclusive `(line, col)` where the span ends, counted in characters
+    ///
+    /// A single-line span's underline is measured in display columns, so it
+    /// is walked along the diagnostic's line of `source`.
+    pub(crate) fn end_position(&self) -> (usize, usize) {
+        self.end_position_in(&self.source)
+    }
+
+    /// Like [`end_position`](Self::end_position), walking the underline
+    /// along the diagnostic's line of `code`
+    pub(crate) fn end_position_in(&self, code: &str) -> (usize, usize) {
+        let span = self.span;
+        match span.end {
+            Some((_, col)) => (self.last_line(), col),
+            None => {
+                let line = position::position_to_offset(code, span.line, 1)
+                    .map_or("", |start| &code[start..position::line_end(code, start)]);
+                let tab_width = self.options.tab_width;
+                let end = text::col_after_width(line, span.col, span.length, tab_width);
+                (span.line, end)
+            }
+        }
+    }
+
+    /// The kind as an [`ErrorKind`], matching well-known titles to their variant
+    pub fn error_kind(&self) -> ErrorKind {
+        ErrorKind::from(&*self.kind)
+    }
+
+    /// Replace the help with several lines, such as the steps of a fix
+    ///
+    /// The first line follows `= help:` and the rest are aligned beneath it.
+    /// An empty list removes the help.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tana_validation::Diagnostic;
+    ///
+    /// let diagnostic = Diagnostic::builder().message("msg").build().with_help_lines(vec![
+    ///     "Import kv from 'tana/kv'".to_string(),
+    ///     "Call kv.get instead".to_string(),
+    /// ]);
+    ///
+    /// assert!(diagnostic
+    ///     .render()
+    ///     .contains("= help: Import kv from 'tana/kv'\n            Call kv.get instead\n"));
+    /// ```
+    pub fn with_help_lines(mut self, lines: Vec<String>) -> Self {
+        self.help = (!lines.is_empty()).then(|| lines.join("\n").into());
+        self
+    }
+
+    /// Replace the help with `intro` followed by `items` as a bulleted list
+    ///
+    /// Each item gets its own `•` line, aligned beneath the intro, which
+    /// reads better than a comma-separated run for long lists. An empty
+    /// intro starts the list on the `= help:` line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tana_validation::Diagnostic;
+    ///
+    /// let diagnostic = Diagnostic::builder().message("msg").build().with_help_list(
+    ///     "Available modules:",
+    ///     vec!["tana/core".to_string(), "tana/kv".to_string()],
+    /// );
+    ///
+    /// assert!(diagnostic.render().contains(
+    ///     "= help: Available modules:\n            • tana/core\n            • tana/kv\n"
+    /// ));
+    /// ```
+    pub fn with_help_list(self, intro: &str, items: Vec<String>) -> Self {
+        let lines = (!intro.is_empty())
+            .then(|| intro.to_string())
+            .into_iter()
+            .chain(items.iter().map(|item| format!("• {}", item)))
+            .collect();
+        self.with_help_lines(lines)
+    }
+
+    /// Build a diagnostic from a byte range of `code`, as reported by the parser
+    ///
+    /// The 1-indexed line and column are computed from `start`, and the
+    /// underline covers the display width of `start..end`, stopping at the end
+    /// of the first line. Offsets past the end of `code` are clamped to it,
+    /// and offsets inside a multibyte character resolve to its start. An
+    /// empty `help` omits the help block.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tana_validation::{Diagnostic, Span};
+    ///
+    /// let code = "import { console } from 'tana/invalid';";
+    /// let diagnostic = Diagnostic::from_byte_span(
+    ///     code,
+    ///     "contract.ts",
+    ///     "Invalid Import",
+    ///     25,
+    ///     37,
+    ///     "Module 'tana/invalid' not found",
+    ///     "",
+    /// );
+    ///
+    /// assert_eq!(diagnostic.span, Span::single_line(1, 26, 12));
+    /// ```
+    pub fn from_byte_span(
+        code: &str,
+        file_path: &str,
+        kind: &str,
+        start: usize,
+        end: usize,
+        message: &str,
+        help: &str,
+    ) -> Self {
+        // A leading byte order mark is not part of the first line
+        let start = position::floor_char_boundary(code, start).max(position::bom_len(code));
+        let end = position::floor_char_boundary(code, end).max(start);
+        let (line, col) = position::offset_to_position(code, start);
+
+        // Measure in display columns along the first line of the span
+        let line_start = code[..start]
+            .rfind('\n')
+            .map_or(position::bom_len(code), |newline| newline + 1);
+        let line_end = position::line_end(code, start);
+        let line_text = &code[line_start..line_end];
+        let end_col = col + code[start..end.min(line_end)].chars().count();
+        let tab_width = FormatOptions::default().tab_width;
+        let underline = text::display_offset(line_text, end_col, tab_width)
+            - text::display_offset(line_text, col, tab_width);
+
+        Diagnostic {
+            source: code.to_string(),
+            file_path: file_path.to_string(),
+            kind: kind.to_string().into(),
+            span: Span::single_line(line, col, underline),
+            message: message.to_string().into(),
+            help: (!help.is_empty()).then(|| help.to_string().into()),
+            ..Diagnostic::default()
+        }
+    }
+
+    /// Render the diagnostic as a Rust/Gleam-style error box
+    pub fn render(&self) -> String {
+        self.render_with(&self.options.box_style.theme())
+    }
+
+    /// Render the diagnostic with custom icons, titles, and glyphs
+    ///
+    /// Uses `theme` in place of the built-in theme selected by the box style;
+    /// the layout is otherwise identical to [`Diagnostic::render`].
+    pub fn render_with(&self, theme: &Theme) -> String {
+        render::render(self, theme)
+    }
+
+    /// Write the rendered error box into an existing buffer
+    ///
+    /// Produces the same text as [`Diagnostic::render`] without allocating a
+    /// new `String`, so one buffer can be reused across many diagnostics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tana_validation::Diagnostic;
+    ///
+    /// let mut buffer = String::new();
+    /// for line in 1..=2 {
+    ///     let diagnostic = Diagnostic::builder().source("a\nb").line(line).build();
+    ///     diagnostic.write_to(&mut buffer).unwrap();
+    /// }
+    ///
+    /// assert_eq!(buffer.matches("Validation Error").count(), 2);
+    /// ```
+    pub fn write_to<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        render::write(w, self, &self.options.box_style.theme())
+    }
+
+    /// Render each piece of the error box separately
+    ///
+    /// The pieces are laid out exactly as in [`Diagnostic::render`], so
+    /// callers can place them in their own UI; written one after another
+    /// they reproduce the full box.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use tana_validation::Diagnostic;
+    ///
+    /// let diagnostic = Diagnostic::builder().source("oops").message("msg").help("fix it").build();
+    /// let parts = diagnostic.parts();
+    ///
+    /// assert_eq!(parts.help, "    = help: fix it\n    │\n");
+    /// assert_eq!(parts.to_string(), diagnostic.render());
+    /// ```
+    pub fn parts(&self) -> DiagnosticParts {
+        render::parts(self, &self.options.box_style.theme())
+    }
+}
+
+/// The separately rendered pieces of a diagnostic's error box, created by
+/// [`Diagnostic::parts`]
+///
+/// Each piece ends with a newline. Displaying the parts writes them in
+/// field order, which is the text of [`Diagnostic::render`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticParts {
+    /// The title line, such as `Validation Error`, after a blank line
+    pub title: String,
+    /// The icon and kind line, followed by a blank line
+    pub kind: String,
+    /// The `┌─ file:line:col` location line and the separator beneath it
+    pub location: String,
+    /// The source rows with their underlines, and any type mismatch and
+    /// suggested fix
+    pub snippet: String,
+    /// The help, note, related and docs blocks; empty when the diagnostic
+    /// has none
+    pub help: String,
+    /// The corner closing the box
+    pub bottom: String,
+}
+
+/// Writes every part in order, reproducing [`Diagnostic::render`]
+impl fmt::Display for DiagnosticParts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in [
+            &self.title,
+            &self.kind,
+            &self.location,
+            &self.snippet,
+            &self.help,
+            &self.bottom,
+        ] {
+            f.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the same text as [`Diagnostic::render`]
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
+}
+
+/// Builder for [`Diagnostic`], created by [`Diagnostic::builder`]
+///
+/// Unset fields keep their defaults: empty text, position `1:1`, and a
+/// single-column underline.
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticBuilder {
+    diagnostic: Diagnostic,
+    position_base: PositionBase,
+}
+
+impl DiagnosticBuilder {
+    /// Set the source code containing the error
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.diagnostic.source = source.into();
+        self
+    }
+
+    /// Set the path of the file the source came from
+    pub fn file(mut self, file_path: impl Into<String>) -> Self {
+        self.diagnostic.file_path = file_path.into();
+        self
+    }
+
+    /// Set the error category shown in the header
+    pub fn kind(mut self, kind: impl Into<Cow<'static, str>>) -> Self {
+        self.diagnostic.kind = kind.into();
+        self
+    }
+
+    /// Identify the failure class with a code shown as `[code]` after the kind
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.diagnostic.code = Some(code.into());
+        self
+    }
+
+    /// Count the lines and columns passed to later [`line`](Self::line) and
+    /// [`col`](Self::col) calls from `base`
+    ///
+    /// Positions are stored and shown 1-indexed whatever the base, so a
+    /// zero-indexed `(0, 25)` points at the same place as the default
+    /// `(1, 26)`. Spans are always 1-indexed and aren't affected.
+    pub fn position_base(mut self, base: PositionBase) -> Self {
+        self.position_base = base;
+        self
+    }
+
+    /// Set the line number
+    ///
+    /// It is 1-indexed unless a [`position_base`](Self::position_base) says
+    /// otherwise.
+    pub fn line(mut self, line: usize) -> Self {
+        self.diagnostic.span.line = self.position_base.to_one_indexed(line);
+        self
+    }
+
+    /// Set the column number
+    ///
+    /// It is 1-indexed unless a [`position_base`](Self::position_base) says
+    /// otherwise. A 1-indexed column of 0 is taken as column 1, so the caret
+    /// sits under the first character.
+    pub fn col(mut self, col: usize) -> Self {
+        self.diagnostic.span.col = self.position_base.to_one_indexed(col).max(1);
+        self
+    }
+
+    /// Set how many display columns to underline
+    pub fn underline(mut self, underline_length: usize) -> Self {
+        self.diagnostic.span.length = underline_length;
+        self
+    }
+
+    /// Point at `span`, replacing the line, column and underline
+    ///
+    /// A start column of 0 is taken as column 1.
+    pub fn span(mut self, span: Span) -> Self {
+        self.diagnostic.span = Span {
+            col: span.col.max(1),
+            ..span
+        };
+        self
+    }
+
+    /// Set the message printed next to the carets
+    pub fn message(mut self, message: impl Into<Cow<'static, str>>) -> Self {
+        self.diagnostic.message = message.into();
+        self
+    }
+
+    /// Set the help text explaining how to fix the problem
+    pub fn help(mut self, help: impl Into<Cow<'static, str>>) -> Self {
+        self.diagnostic.help = Some(help.into());
+        self
+    }
+
+    /// Set a note giving more background on the problem
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.diagnostic.note = Some(note.into());
+        self
+    }
+
+    /// Link to a page explaining the error, shown as `= see {url}` at the end
+    pub fn docs_url(mut self, url: impl Into<String>) -> Self {
+        self.diagnostic.docs_url = Some(url.into());
+        self
+    }
+
+    /// Point at another location related to the error
+    pub fn secondary_label(mut self, label: Label) -> Self {
+        self.diagnostic.secondary_labels.push(label);
+        self
+    }
+
+    /// Cite a position elsewhere, such as where a type was defined
+    pub fn related(mut self, location: RelatedLocation) -> Self {
+        self.diagnostic.related.push(location);
+        self
+    }
+
+    /// Propose replacement text that fixes the problem
+    pub fn suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.diagnostic.suggestion = Some(suggestion);
+        self
+    }
+
+    /// Compare the expected and found types beneath the snippet
+    pub fn type_mismatch(mut self, mismatch: TypeMismatch) -> Self {
+        self.diagnostic.type_mismatch = Some(mismatch);
+        self
+    }
+
+    /// Set how serious the problem is
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.diagnostic.severity = severity;
+        self
+    }
+
+    /// Show up to `lines` lines of surrounding source above and below the error
+    pub fn context_lines(mut self, lines: usize) -> Self {
+        self.diagnostic.options.context_lines = lines;
+        self
+    }
+
+    /// Show the nearest non-blank line above the error line, without an underline
+    pub fn show_prev_line(mut self, show: bool) -> Self {
+        self.diagnostic.options.show_prev_line = show;
+        self
+    }
+
+    /// Cut source lines wider than `width` display columns, or pass `None` to print them whole
+    pub fn max_line_width(mut self, width: impl Into<Option<usize>>) -> Self {
+        self.diagnostic.options.max_line_width = width.into();
+        self
+    }
+
+    /// Word-wrap help text wider than `width` display columns, or pass `None` to keep it as given
+    pub fn wrap_width(mut self, width: impl Into<Option<usize>>) -> Self {
+        self.diagnostic.options.wrap_width = width.into();
+        self
+    }
+
+    /// Fit the whole box within `width` display columns, or pass `None` for no limit
+    pub fn render_width(mut self, width: impl Into<Option<usize>>) -> Self {
+        self.diagnostic.options.render_width = width.into();
+        self
+    }
+
+    /// Wrap text containing right-to-left scripts in directional isolates
+    /// so the box keeps its left-to-right layout
+    pub fn isolate_bidi(mut self, isolate: bool) -> Self {
+        self.diagnostic.options.isolate_bidi = isolate;
+        self
+    }
+
+    /// Follow each message with the column it points at, as in `(col 26)`
+    pub fn show_columns(mut self, show: bool) -> Self {
+        self.diagnostic.options.show_columns = show;
+        self
+    }
+
+    /// Add `offset` to every displayed line number, for a snippet that
+    /// starts on line `offset + 1` of a larger document
+    pub fn line_offset(mut self, offset: usize) -> Self {
+        self.diagnostic.options.line_offset = offset;
+        self
+    }
+
+    /// Print messages after their carets or on the rows below them
+    pub fn label_placement(mut self, placement: LabelPlacement) -> Self {
+        self.diagnostic.options.label_placement = placement;
+        self
+    }
+
+    /// Leave out the blank line before the title and the separator above the bottom corner
+    pub fn trim_surrounding(mut self, trim: bool) -> Self {
+        self.diagnostic.options.trim_surrounding = trim;
+        self
+    }
+
+    /// Make the location line a clickable terminal link opening the file
+    pub fn hyperlinks(mut self, hyperlinks: Hyperlinks) -> Self {
+        self.diagnostic.options.hyperlinks = hyperlinks;
+        self
+    }
+
+    /// Show file paths under `root` relative to it in the location line
+    ///
+    /// The stored `file_path` is unchanged; only its display is shortened.
+    pub fn workspace_root(mut self, root: impl Into<String>) -> Self {
+        self.diagnostic.options.workspace_root = Some(root.into());
+        self
+    }
+
+    /// Choose between Unicode box drawing and plain ASCII output
+    pub fn box_style(mut self, box_style: BoxStyle) -> Self {
+        self.diagnostic.options.box_style = box_style;
+        self
+    }
+
+    /// Set the presentation settings used when rendering
+    pub fn options(mut self, options: FormatOptions) -> Self {
+        self.diagnostic.options = options;
+        self
+    }
+
+    /// Choose when ANSI colors are emitted while rendering
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.diagnostic.color = color;
+        self
+    }
+
+    /// Finish building the diagnostic
+    pub fn build(self) -> Diagnostic {
+        self.diagnostic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format_validation_error, format_validation_error_with_options};
+
+    #[test]
+    fn test_builder_matches_legacy_function() {
+        let code = "import { console } from 'tana/invalid';";
+        let legacy = format_validation_error(
+            code,
+            "contract.ts",
+            "Invalid Import",
+            1,
+            26,
+            "Module 'tana/invalid' not found",
+            "Available modules: tana/core, tana/kv",
+            12,
+        );
+
+        let diagnostic = Diagnostic::builder()
+            .source(code)
+            .file("contract.ts")
+            .kind("Invalid Import")
+            .line(1)
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .build();
+
+        assert_eq!(diagnostic.render(), legacy);
+        assert_eq!(diagnostic.to_string(), legacy);
+    }
+
+    #[test]
+    fn test_sort_orders_by_position_then_severity() {
+        let at = |file: &str, line, col, severity| {
+            Diagnostic::builder()
+                .file(file)
+                .line(line)
+                .col(col)
+                .severity(severity)
+                .message(format!("{}:{}:{} {:?}", file, line, col, severity))
+                .build()
+        };
+        let mut diagnostics = [
+            at("b.ts", 1, 1, Severity::Error),
+            at("a.ts", 2, 1, Severity::Hint),
+            at("a.ts", 1, 5, Severity::Warning),
+            at("a.ts", 1, 5, Severity::Error),
+            at("a.ts", 1, 2, Severity::Info),
+            at("a.ts", 10, 1, Severity::Error),
+        ];
+
+        diagnostics.sort();
+
+        let order: Vec<&str> = diagnostics.iter().map(|d| &*d.message).collect();
+        assert_eq!(
+            order,
+            [
+                "a.ts:1:2 Info",
+                "a.ts:1:5 Error",
+                "a.ts:1:5 Warning",
+                "a.ts:2:1 Hint",
+                "a.ts:10:1 Error",
+                "b.ts:1:1 Error",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_order_agrees_with_equality() {
+        let at =
+            |message: &'static str| Diagnostic::builder().file("a.ts").message(message).build();
+        let (a, b) = (at("a"), at("b"));
+
+        assert_eq!(a.cmp(&b), std::cmp::Ordering::Less);
+        assert_eq!(b.cmp(&a), std::cmp::Ordering::Greater);
+        assert_eq!(a.cmp(&a.clone()), std::cmp::Ordering::Equal);
+
+        let mut fixed = a.clone();
+        fixed.suggestion = Some(Suggestion::new(1, 1, 1, "x"));
+        assert_ne!(a.cmp(&fixed), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_single_line_span_matches_legacy() {
+        let code = "const x = 1;\nlet y: number = \"2\";";
+        let legacy = format_validation_error(
+            code,
+            "contract.ts",
+            "Type Error",
+            2,
+            17,
+            "expected number",
+            "remove the quotes",
+            3,
+        );
+
+        let diagnostic = Diagnostic::builder()
+            .source(code)
+            .file("contract.ts")
+            .kind("Type Error")
+            .span(Span::single_line(2, 17, 3))
+            .message("expected number")
+            .help("remove the quotes")
+            .build();
+
+        assert_eq!(diagnostic.render(), legacy);
+        assert_eq!(diagnostic.span, Span::single_line(2, 17, 3));
+    }
+
+    #[test]
+    fn test_single_line_span_matches_legacy_on_long_lines() {
+        // Past the default 120-column cap both forms clip the same window
+        let code = format!("let x = \"{}\" + y;", "a".repeat(200));
+        let legacy = format_validation_error(
+            &code,
+            "contract.ts",
+            "Type Error",
+            1,
+            214,
+            "cannot find 'y'",
+            "declare it first",
+            1,
+        );
+        let span = crate::format_validation_error_span(
+            &code,
+            "contract.ts",
+            "Type Error",
+            1,
+            214,
+            1,
+            215,
+            "cannot find 'y'",
+            "declare it first",
+        );
+
+        assert!(legacy.contains('…'));
+        assert_eq!(span, legacy);
+    }
+
+    #[test]
+    fn test_zero_indexed_position_matches_one_indexed() {
+        let code = "import { console } from 'tana/invalid';";
+        let at = |base, line, col| {
+            Diagnostic::builder()
+                .source(code)
+                .file("contract.ts")
+                .position_base(base)
+                .line(line)
+                .col(col)
+                .underline(14)
+                .message("not found")
+                .build()
+        };
+
+        let zero = at(PositionBase::Zero, 0, 25);
+        let one = at(PositionBase::One, 1, 26);
+        assert_eq!((zero.span.line, zero.span.col), (1, 26));
+        assert_eq!(zero.render(), one.render());
+        assert!(zero.render().contains("┌─ contract.ts:1:26\n"));
+    }
+
+    #[test]
+    fn test_builder_matches_legacy_with_options() {
+        let code = "fn main() {\n\tlet x = oops;\n}";
+        let options = FormatOptions {
+            tab_width: 2,
+            ..FormatOptions::default()
+        };
+        let legacy = format_validation_error_with_options(
+            code,
+            "tabs.ts",
+            "Type Error",
+            2,
+            10,
+            "msg",
+            "help",
+            4,
+            &options,
+        );
+
+        let diagnostic = Diagnostic::builder()
+            .source(code)
+            .file("tabs.ts")
+            .kind("Type Error")
+            .line(2)
+            .col(10)
+            .underline(4)
+            .message("msg")
+            .help("help")
+            .options(options)
+            .build();
+
+        assert_eq!(diagnostic.render(), legacy);
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let diagnostic = Diagnostic::builder().build();
+
+        assert_eq!(diagnostic.span.line, 1);
+        assert_eq!(diagnostic.span.col, 1);
+        assert_eq!(diagnostic.span.length, 1);
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.help, None);
+        assert_eq!(diagnostic, Diagnostic::default());
+    }
+
+    #[test]
+    fn test_error_kind_matches_known_titles() {
+        assert_eq!(ErrorKind::from("Invalid Import"), ErrorKind::InvalidImport);
+        assert_eq!(ErrorKind::from("type_error"), ErrorKind::TypeError);
+        assert_eq!(ErrorKind::from("SyntaxError"), ErrorKind::SyntaxError);
+        assert_eq!(
+            ErrorKind::from("Invlaid Import"),
+            ErrorKind::Other("Invlaid Import".to_string())
+        );
+        assert_eq!(ErrorKind::MissingExport.to_string(), "Missing Export");
+
+        // The legacy function shows a matched kind by its canonical title
+        let rendered = format_validation_error(
+            "import { x } from 'tana/nope';",
+            "contract.ts",
+            "invalid import",
+            1,
+            19,
+            "msg",
+            "",
+            11,
+        );
+        assert!(rendered.contains("❌ Invalid Import\n"));
+
+        let diagnostic = Diagnostic::builder()
+            .kind(ErrorKind::ForbiddenGlobal)
+            .build();
+        assert!(matches!(diagnostic.kind, Cow::Borrowed("Forbidden Global")));
+        assert_eq!(diagnostic.error_kind(), ErrorKind::ForbiddenGlobal);
+        let custom = Diagnostic::builder()
+            .kind(ErrorKind::Other("Gas Limit".to_string()))
+            .build();
+        assert_eq!(custom.kind, "Gas Limit");
+    }
+
+    #[test]
+    fn test_static_text_is_borrowed() {
+        let diagnostic = Diagnostic::builder()
+            .kind("Invalid Import")
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .build();
+
+        assert!(matches!(diagnostic.kind, Cow::Borrowed("Invalid Import")));
+        assert!(matches!(
+            diagnostic.message,
+            Cow::Borrowed("Module 'tana/invalid' not found")
+        ));
+        assert!(matches!(diagnostic.help, Some(Cow::Borrowed(_))));
+
+        let owned = Diagnostic::builder()
+            .message(format!("Module '{}' not found", "tana/nope"))
+            .build();
+        assert!(matches!(owned.message, Cow::Owned(_)));
+        assert_eq!(owned.message, "Module 'tana/nope' not found");
+    }
+
+    #[test]
+    fn test_point_renders_single_caret() {
+        let diagnostic = Diagnostic {
+            source: "let x = 1\nlet y = 2".to_string(),
+            message: "expected `;`".into(),
+            ..Diagnostic::point(2, 10)
+        };
+        let rendered = diagnostic.render();
+
+        assert_eq!(diagnostic.span.length, 0);
+        assert_eq!(rendered.matches('^').count(), 1);
+        assert!(rendered.contains("  2 │ let y = 2\n    │          ^ expected `;`\n"));
+    }
+
+    #[test]
+    fn test_help_lines_share_the_gutter() {
+        let rendered = Diagnostic::builder()
+            .source("kv.put(1, 2)")
+            .message("msg")
+            .build()
+            .with_help_lines(vec![
+                "Convert the key to a string".to_string(),
+                "Or use kv.putNumber".to_string(),
+                "See the kv docs".to_string(),
+            ])
+            .render();
+
+        assert!(rendered.contains(
+            "    │\n    = help: Convert the key to a string\n            Or use kv.putNumber\n            See the kv docs\n    │\n    └─\n"
+        ));
+        assert_eq!(Diagnostic::default().with_help_lines(Vec::new()).help, None);
+    }
+
+    #[test]
+    fn test_parts_concatenate_to_render() {
+        let diagnostic = Diagnostic::builder()
+            .source("import { x } from 'tana/nope';\nconsole.log(x);")
+            .file("contract.ts")
+            .kind("Invalid Import")
+            .code("T0001")
+            .col(19)
+            .underline(11)
+            .message("Module 'tana/nope' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .note("modules are case-sensitive")
+            .secondary_label(Label::new(2, 13, 1, "used here"))
+            .suggestion(Suggestion::new(1, 20, 9, "tana/kv"))
+            .build();
+        let parts = diagnostic.parts();
+
+        assert_eq!(parts.to_string(), diagnostic.render());
+        let concatenated = [
+            parts.title.as_str(),
+            &parts.kind,
+            &parts.location,
+            &parts.snippet,
+            &parts.help,
+            &parts.bottom,
+        ]
+        .concat();
+        assert_eq!(concatenated, diagnostic.render());
+
+        assert_eq!(parts.kind, "❌ Invalid Import [T0001]\n\n");
+        assert_eq!(parts.location, "    ┌─ contract.ts:1:19\n    │\n");
+        assert!(parts.snippet.starts_with("  1 │ import"));
+        assert!(parts.help.starts_with("    = help: Available modules"));
+        assert_eq!(parts.bottom, "    └─\n");
+
+        let bare = Diagnostic::builder().source("x").build().parts();
+        assert_eq!(bare.help, "");
+    }
+
+    #[test]
+    fn test_help_list_puts_each_item_on_a_bullet() {
+        let modules = vec![
+            "tana/core".to_string(),
+            "tana/kv".to_string(),
+            "tana/block".to_string(),
+        ];
+        let rendered = Diagnostic::builder()
+            .source("import { x } from 'tana/nope';")
+            .message("msg")
+            .build()
+            .with_help_list("Available modules:", modules.clone())
+            .render();
+
+        assert!(rendered.contains(
+            "    = help: Available modules:\n            • tana/core\n            • tana/kv\n            • tana/block\n    │\n"
+        ));
+
+        let bare = Diagnostic::default().with_help_list("", modules);
+        assert_eq!(
+            bare.help.as_deref(),
+            Some("• tana/core\n• tana/kv\n• tana/block")
+        );
+        assert_eq!(
+            Diagnostic::default().with_help_list("", Vec::new()).help,
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_byte_span_after_multibyte_character() {
+        let code = "let a = 1;\nlet 名前 = oops;\n";
+        let start = code.find("oops").unwrap();
+        let diagnostic =
+            Diagnostic::from_byte_span(code, "span.ts", "Type Error", start, start + 4, "msg", "");
+
+        // 名前 is six bytes but two characters, so the column is 10, not 14
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (2, 10));
+        assert_eq!(diagnostic.span.length, 4);
+        assert_eq!(diagnostic.help, None);
+        assert!(diagnostic
+            .render()
+            .contains("  2 │ let 名前 = oops;\n    │            ^^^^ msg\n"));
+    }
+
+    #[test]
+    fn test_from_byte_span_across_crlf() {
+        let code = "ab\r\ncd";
+
+        let on_c = Diagnostic::from_byte_span(code, "a.ts", "Error", 4, 5, "msg", "");
+        assert_eq!((on_c.span.line, on_c.span.col, on_c.span.length), (2, 1, 1));
+
+        // A span running over the terminator stops at the end of its first line
+        let across = Diagnostic::from_byte_span(code, "a.ts", "Error", 1, 5, "msg", "");
+        assert_eq!(
+            (across.span.line, across.span.col, across.span.length),
+            (1, 2, 1)
+        );
+        assert!(across.render().contains("  1 │ ab\n    │  ^ msg\n"));
+    }
+
+    #[test]
+    fn test_from_byte_span_clamps_past_eof() {
+        let code = "let a = 1;";
+        let diagnostic = Diagnostic::from_byte_span(code, "", "", 500, 900, "msg", "help");
+
+        assert_eq!((diagnostic.span.line, diagnostic.span.col), (1, 11));
+        assert_eq!(diagnostic.span.length, 0);
+        assert_eq!(diagnostic.help.as_deref(), Some("help"));
+    }
+
+    #[test]
+    fn test_help_present_and_absent() {
+        let with_help = Diagnostic::builder()
+            .source("let x = 1;")
+            .kind("Unused Binding")
+            .col(5)
+            .message("msg")
+            .help("Remove it")
+            .build()
+            .render();
+        assert!(with_help.ends_with("    │\n    = help: Remove it\n    │\n    └─\n"));
+
+        let without_help = Diagnostic::builder()
+            .source("let x = 1;")
+            .kind("Unused Binding")
+            .col(5)
+            .message("msg")
+            .build()
+            .render();
+        assert!(!without_help.contains("help"));
+        assert!(without_help.ends_with("    │     ^ msg\n    │\n    └─\n"));
+
+        // The legacy function treats an empty help string as no help
+        let legacy =
+            format_validation_error("let x = 1;", "", "Unused Binding", 1, 5, "msg", "", 1);
+        assert_eq!(legacy, without_help);
+    }
+
+    #[test]
+    fn test_severity_icons_and_titles() {
+        let build = |severity| {
+            Diagnostic::builder()
+                .source("let x = 1;")
+                .file("contract.ts")
+                .kind("Unused Binding")
+                .line(1)
+                .col(5)
+                .message("msg")
+                .help("help")
+                .severity(severity)
+                .build()
+                .render()
+        };
+        let error = build(Severity::Error);
+
+        let cases = [
+            (Severity::Error, "❌", "Validation Error", "^"),
+            (Severity::Warning, "⚠️", "Validation Warning", "~"),
+            (Severity::Info, "ℹ️", "Validation Info", "-"),
+            (Severity::Hint, "💡", "Validation Hint", "-"),
+        ];
+        for (severity, icon, title, caret) in cases {
+            let rendered = build(severity);
+            assert!(rendered.starts_with(&format!("\n{}\n{} Unused Binding\n", title, icon)));
+
+            // Everything below the header but the caret is shared across severities
+            let body = |s: &str| s.split_once("\n\n").unwrap().1.to_string();
+            assert_eq!(body(&rendered), body(&error).replace('^', caret));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_severity_name_matches_serde() {
+        for severity in Severity::ALL {
+            assert_eq!(serde_json::to_value(severity).unwrap(), severity.name());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let diagnostic = Diagnostic::builder()
+            .source("let total: number = \"ten\";\n")
+            .file("contracts/types.ts")
+            .kind("Type Mismatch")
+            .line(1)
+            .col(21)
+            .underline(5)
+            .message("expected number, found string")
+            .help("remove the quotes")
+            .note("declared as number")
+            .secondary_label(Label::new(1, 12, 6, "declared here"))
+            .suggestion(Suggestion::new(1, 21, 5, "10"))
+            .severity(Severity::Warning)
+            .context_lines(2)
+            .hyperlinks(Hyperlinks::Vscode)
+            .workspace_root("/work")
+            .color(ColorChoice::Always)
+            .build();
+
+        let json = serde_json::to_value(&diagnostic).unwrap();
+        assert_eq!(json["span"]["length"], 5);
+        assert_eq!(json["severity"], "warning");
+        assert_eq!(json["secondaryLabels"][0]["message"], "declared here");
+        assert_eq!(json["options"]["contextLines"], 2);
+
+        let back: Diagnostic = serde_json::from_value(json).unwrap();
+        assert_eq!(back, diagnostic);
+    }
+}
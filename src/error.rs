@@ -0,0 +1,113 @@
+//! A validation failure usable as a standard Rust error
+
+use std::error::Error;
+use std::fmt;
+
+use crate::Diagnostic;
+
+/// A [`Diagnostic`] that can be propagated with `?`
+///
+/// `ValidationError` implements [`std::error::Error`], so it converts into
+/// `Box<dyn Error>` and composes with crates like `anyhow` and `thiserror`.
+/// Its `Display` output is the full error box, identical to
+/// [`Diagnostic::render`].
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, ValidationError};
+///
+/// fn check(code: &str) -> Result<(), ValidationError> {
+///     if code.contains("eval") {
+///         let diagnostic = Diagnostic::builder()
+///             .source(code)
+///             .kind("Forbidden Call")
+///             .message("eval is not allowed")
+///             .build();
+///         return Err(diagnostic.into());
+///     }
+///     Ok(())
+/// }
+///
+/// let error = check("eval('1')").unwrap_err();
+/// assert!(error.to_string().contains("eval is not allowed"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    // Boxed so `Result<T, ValidationError>` stays pointer-sized
+    diagnostic: Box<Diagnostic>,
+}
+
+impl ValidationError {
+    /// The diagnostic describing the failure
+    pub fn diagnostic(&self) -> &Diagnostic {
+        &self.diagnostic
+    }
+
+    /// Take back the diagnostic describing the failure
+    pub fn into_diagnostic(self) -> Diagnostic {
+        *self.diagnostic
+    }
+}
+
+impl From<Diagnostic> for ValidationError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self {
+            diagnostic: Box::new(diagnostic),
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.diagnostic.write_to(f)
+    }
+}
+
+impl Error for ValidationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format_validation_error;
+
+    fn validate(code: &str) -> Result<(), ValidationError> {
+        Err(Diagnostic::builder()
+            .source(code)
+            .file("contract.ts")
+            .kind("Invalid Import")
+            .line(1)
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .build()
+            .into())
+    }
+
+    fn run(code: &str) -> Result<(), Box<dyn Error>> {
+        validate(code)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_propagates_with_question_mark() {
+        let code = "import { console } from 'tana/invalid';";
+        let error = run(code).unwrap_err();
+
+        let expected = format_validation_error(
+            code,
+            "contract.ts",
+            "Invalid Import",
+            1,
+            26,
+            "Module 'tana/invalid' not found",
+            "Available modules: tana/core, tana/kv",
+            12,
+        );
+        assert_eq!(error.to_string(), expected);
+
+        let error = error.downcast::<ValidationError>().unwrap();
+        assert_eq!(error.diagnostic().kind, "Invalid Import");
+    }
+}
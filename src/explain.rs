@@ -0,0 +1,101 @@
+//! Extended explanations of the well-known error categories
+
+use crate::ErrorKind;
+
+/// Longer prose explanation of `kind`, for tooling to show on demand
+///
+/// Like `rustc --explain`, each well-known [`ErrorKind`] has a few
+/// paragraphs covering what it means, what commonly causes it, and how to
+/// fix it. Paragraphs are separated by a blank line. Categories outside the
+/// catalog, [`ErrorKind::Other`], have no explanation.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{explain, ErrorKind};
+///
+/// let text = explain(&ErrorKind::InvalidImport).unwrap();
+/// assert!(text.starts_with("A contract imported a module"));
+/// assert_eq!(explain(&ErrorKind::Other("Gas Limit".to_string())), None);
+/// ```
+pub fn explain(kind: &ErrorKind) -> Option<&'static str> {
+    match kind {
+        ErrorKind::InvalidImport => Some(INVALID_IMPORT),
+        ErrorKind::TypeError => Some(TYPE_ERROR),
+        ErrorKind::ForbiddenGlobal => Some(FORBIDDEN_GLOBAL),
+        ErrorKind::MissingExport => Some(MISSING_EXPORT),
+        ErrorKind::SyntaxError => Some(SYNTAX_ERROR),
+        ErrorKind::Other(_) => None,
+    }
+}
+
+const INVALID_IMPORT: &str = "\
+A contract imported a module that the Tana runtime does not provide.
+
+Contracts run in a sandbox that only offers the `tana/` standard modules, \
+such as `tana/core`, `tana/kv`, `tana/block` and `tana/tx`. Packages from \
+npm, relative files and Node built-ins can't be loaded. The most common \
+cause is a typo in the module name, like `tana/kvs` for `tana/kv`.
+
+Check the spelling against the list of available modules shown in the help, \
+and import from one of them. Code from elsewhere has to be copied into the \
+contract itself.";
+
+const TYPE_ERROR: &str = "\
+A value was used where a value of a different type was expected.
+
+This usually means a function received an argument of the wrong type, a \
+property was read from `undefined` or `null`, or a stored value was read \
+back with a different shape than it was written with.
+
+Look at the underlined expression and the type it was expected to have. \
+Convert the value explicitly, check for missing values before using them, \
+or fix the place where the value was produced.";
+
+const FORBIDDEN_GLOBAL: &str = "\
+A contract used a global that isn't available inside the sandbox.
+
+Contracts must run the same way on every node, so globals that reach the \
+host or vary between machines, such as `window`, `process`, `fetch` or \
+`eval`, are removed. Code copied from a browser or Node project often uses \
+them without anyone noticing.
+
+Replace the global with the equivalent from a `tana/` module, for example \
+reading the block time from `tana/block` instead of the system clock, or \
+remove the code that needs it.";
+
+const MISSING_EXPORT: &str = "\
+A contract does not export the function the runtime calls to run it.
+
+The runtime looks up the contract's entry point by name. If that name isn't \
+exported, because the function was never defined, was misspelled, or was \
+declared without `export`, the contract can't be invoked.
+
+Export the entry point, either by writing `export` before its declaration \
+or by listing it in an `export { ... }` clause.";
+
+const SYNTAX_ERROR: &str = "\
+The contract's source could not be parsed.
+
+Common causes are an unclosed bracket, brace or string, a missing comma \
+between arguments or properties, or syntax from a language version the \
+runtime doesn't support. The underlined position is where the parser gave \
+up, which can be some way after the actual mistake.
+
+Look at the underlined code and the lines just before it, and fix the first \
+problem there; later errors often disappear with it.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_kinds_are_explained() {
+        for kind in ErrorKind::KNOWN {
+            let text = explain(&kind).unwrap();
+            assert!(!text.trim().is_empty(), "{} has no explanation", kind);
+            assert!(text.contains("\n\n"), "{} has a single paragraph", kind);
+        }
+        assert_eq!(explain(&ErrorKind::Other("Gas Limit".to_string())), None);
+    }
+}
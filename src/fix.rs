@@ -0,0 +1,181 @@
+//! Applying suggested fixes to source code
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+use crate::{position, Suggestion};
+
+/// Two suggestions given to [`apply_suggestions`] that change the same text
+///
+/// Applying both would leave the result depending on their order, so
+/// neither is applied. The suggestions are in source order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlappingSuggestions {
+    /// The suggestion starting first
+    pub first: Suggestion,
+    /// The suggestion starting inside the first one's span
+    pub second: Suggestion,
+}
+
+impl fmt::Display for OverlappingSuggestions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "suggestions at {}:{} and {}:{} overlap",
+            self.first.line, self.first.col, self.second.line, self.second.col
+        )
+    }
+}
+
+impl Error for OverlappingSuggestions {}
+
+/// Apply one suggested fix to `code` and return the fixed source
+///
+/// The replacement is spliced in where the suggestion's span lies, clamped
+/// to its line as in the rendered suggestion, so the rest of `code`,
+/// including its line endings, is unchanged. A suggestion pointing past the
+/// end of `code` changes nothing.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{apply_suggestion, Suggestion};
+///
+/// let code = "let a = 1;\nimport { kv } from 'tana/kvs';\n";
+/// let fixed = apply_suggestion(code, &Suggestion::new(2, 21, 8, "tana/kv"));
+///
+/// assert_eq!(fixed, "let a = 1;\nimport { kv } from 'tana/kv';\n");
+/// ```
+pub fn apply_suggestion(code: &str, suggestion: &Suggestion) -> String {
+    let mut fixed = code.to_string();
+    if let Some(range) = byte_range(code, suggestion) {
+        fixed.replace_range(range, &suggestion.replacement);
+    }
+    fixed
+}
+
+/// Apply several suggested fixes to `code` and return the fixed source
+///
+/// The suggestions may come in any order. They are applied from the last
+/// position in the source to the first, so each splice leaves the positions
+/// of those still to come valid. Insertions at the same position keep the
+/// order they were given in. If any two suggestions change the same text,
+/// nothing is applied and the first such pair is returned as an error.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{apply_suggestions, Suggestion};
+///
+/// let code = "import { kv } from 'tana/kvs';\nkv.gett('a');";
+/// let fixes = [
+///     Suggestion::new(2, 4, 4, "get"),
+///     Suggestion::new(1, 21, 8, "tana/kv"),
+/// ];
+///
+/// assert_eq!(
+///     apply_suggestions(code, &fixes).unwrap(),
+///     "import { kv } from 'tana/kv';\nkv.get('a');"
+/// );
+/// ```
+pub fn apply_suggestions(
+    code: &str,
+    suggestions: &[Suggestion],
+) -> Result<String, OverlappingSuggestions> {
+    let mut edits: Vec<(Range<usize>, &Suggestion)> = suggestions
+        .iter()
+        .filter_map(|suggestion| Some((byte_range(code, suggestion)?, suggestion)))
+        .collect();
+    edits.sort_by_key(|(range, _)| (range.start, range.end));
+
+    // Sorted by start, an edit overlaps an earlier one exactly when it
+    // starts before the furthest end seen so far
+    let mut furthest: Option<(usize, &Suggestion)> = None;
+    for (range, suggestion) in &edits {
+        if let Some((_, first)) = furthest.filter(|&(end, _)| range.start < end) {
+            return Err(OverlappingSuggestions {
+                first: first.clone(),
+                second: (*suggestion).clone(),
+            });
+        }
+        if furthest.is_none_or(|(end, _)| range.end > end) {
+            furthest = Some((range.end, suggestion));
+        }
+    }
+
+    let mut fixed = code.to_string();
+    for (range, suggestion) in edits.into_iter().rev() {
+        fixed.replace_range(range, &suggestion.replacement);
+    }
+    Ok(fixed)
+}
+
+/// Bytes of `code` the suggestion replaces, clamped to its line; `None`
+/// when its line is past the end of `code`
+fn byte_range(code: &str, suggestion: &Suggestion) -> Option<Range<usize>> {
+    let line_start = position::position_to_offset(code, suggestion.line, 1)?;
+    let text = &code[line_start..position::line_end(code, line_start)];
+    let offset = |chars: usize| {
+        text.char_indices()
+            .nth(chars)
+            .map_or(text.len(), |(index, _)| index)
+    };
+
+    let start = suggestion.col.saturating_sub(1);
+    Some(line_start + offset(start)..line_start + offset(start + suggestion.length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_fix_is_spliced_in() {
+        let code = "import { kv } from 'tana/kvs';\r\nkv.get('名前');";
+
+        assert_eq!(
+            apply_suggestion(code, &Suggestion::new(1, 21, 8, "tana/kv")),
+            "import { kv } from 'tana/kv';\r\nkv.get('名前');"
+        );
+        // Spans are counted in characters and clamped to their line
+        assert_eq!(
+            apply_suggestion(code, &Suggestion::new(2, 9, 99, "name');")),
+            "import { kv } from 'tana/kvs';\r\nkv.get('name');"
+        );
+        assert_eq!(apply_suggestion(code, &Suggestion::new(5, 1, 1, "x")), code);
+    }
+
+    #[test]
+    fn test_non_overlapping_fixes_apply_together() {
+        let code = "const a = kv.gett('a');\nconst b = kv.gett('b');";
+        let fixes = [
+            Suggestion::new(2, 14, 4, "get"),
+            Suggestion::new(1, 14, 4, "get"),
+            Suggestion::new(1, 1, 0, "// fixed\n"),
+        ];
+
+        assert_eq!(
+            apply_suggestions(code, &fixes).unwrap(),
+            "// fixed\nconst a = kv.get('a');\nconst b = kv.get('b');"
+        );
+        assert_eq!(apply_suggestions(code, &[]).unwrap(), code);
+    }
+
+    #[test]
+    fn test_overlapping_fixes_are_rejected() {
+        let code = "import { kv } from 'tana/kvs';";
+        let whole = Suggestion::new(1, 20, 10, "'tana/kv'");
+        let inner = Suggestion::new(1, 26, 3, "kv");
+
+        let error = apply_suggestions(code, &[inner.clone(), whole.clone()]).unwrap_err();
+        assert_eq!(
+            error,
+            OverlappingSuggestions {
+                first: whole,
+                second: inner
+            }
+        );
+        assert_eq!(error.to_string(), "suggestions at 1:20 and 1:26 overlap");
+    }
+}
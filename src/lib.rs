@@ -5,6 +5,61 @@
 
 use wasm_bindgen::prelude::*;
 
+mod bag;
+mod checks;
+mod color;
+mod diagnostic;
+mod error;
+mod explain;
+mod fix;
+mod macros;
+mod options;
+mod output;
+mod position;
+#[cfg(not(target_arch = "wasm32"))]
+mod print;
+mod render;
+mod report;
+mod suggest;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod text;
+mod theme;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+pub use bag::DiagnosticBag;
+pub use checks::{
+    check_console_usage, check_entry_point, check_forbidden_globals, check_kv_keys,
+    check_top_level_await, validate_all, validate_imports, ValidationConfig,
+};
+pub use color::{ColorChoice, ColorDepth};
+pub use diagnostic::{
+    Diagnostic, DiagnosticBuilder, DiagnosticParts, ErrorKind, Label, RelatedLocation, Severity,
+    Span, Suggestion, TypeMismatch,
+};
+pub use error::ValidationError;
+pub use explain::explain;
+pub use fix::{apply_suggestion, apply_suggestions, OverlappingSuggestions};
+pub use options::{
+    BoxStyle, FormatOptions, Hyperlinks, LabelPlacement, DEFAULT_MAX_LINE_WIDTH, DEFAULT_TAB_WIDTH,
+};
+pub use output::{
+    format_fix_diff, format_inline, format_oneline, to_github_annotation, to_lsp_json,
+    to_lsp_utf16, to_monaco_marker, to_sarif, to_tap,
+};
+pub use position::{offset_to_position, position_to_offset, PositionBase};
+#[cfg(not(target_arch = "wasm32"))]
+pub use print::print_diagnostic;
+pub use report::{
+    dedupe, dedupe_merging_help, format_file_report, format_grouped_report, format_report,
+    format_report_with_limit,
+};
+pub use suggest::{invalid_import, suggest_closest};
+pub use theme::{Labels, Theme};
+#[cfg(target_arch = "wasm32")]
+pub use wasm::{format_report_json, get_diagnostic_json};
+
 /// Format a validation error with beautiful Rust/Gleam-style output
 ///
 /// This function creates consistent error messages across all Tana systems:
@@ -17,12 +72,19 @@ use wasm_bindgen::prelude::*;
 ///
 /// * `code` - The source code containing the error
 /// * `file_path` - Path to the file (e.g., "contract.ts")
-/// * `error_kind` - Category of error (e.g., "Invalid Import", "Type Error")
+/// * `error_kind` - Category of error (e.g., "Invalid Import", "Type Error");
+///   well-known kinds are shown with their canonical [`ErrorKind`] title
 /// * `line_num` - Line number (1-indexed)
-/// * `col_num` - Column number (1-indexed)
+/// * `col_num` - Column number (1-indexed, counted in characters); 0 is
+///   taken as column 1, with the caret under the first character
 /// * `message` - Error message
 /// * `help` - Help text explaining how to fix
-/// * `underline_length` - Number of characters to underline (for ^^^)
+/// * `underline_length` - Number of display columns to underline (for ^^^)
+///
+/// Caret padding is measured in terminal display columns, so wide characters
+/// such as CJK ideographs and emoji before the error keep the carets aligned.
+/// Tabs in the source line are expanded to [`DEFAULT_TAB_WIDTH`] columns; use
+/// [`format_validation_error_with_options`] to choose a different width.
 ///
 /// # Example
 ///
@@ -44,16 +106,17 @@ use wasm_bindgen::prelude::*;
 /// // Validation Error
 /// // ❌ Invalid Import
 /// //
-/// // ┌─ contract.ts:1:26
-/// // │
+/// //     ┌─ contract.ts:1:26
+/// //     │
 /// //   1 │ import { console } from 'tana/invalid';
 /// //     │                          ^^^^^^^^^^^^ Module 'tana/invalid' not found
-/// // │
-/// // = help: Available modules: tana/core, tana/kv, tana/block
-/// // │
-/// // └─
+/// //     │
+/// //     = help: Available modules: tana/core, tana/kv, tana/block
+/// //     │
+/// //     └─
 /// ```
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn format_validation_error(
     code: &str,
     file_path: &str,
@@ -64,12 +127,154 @@ pub fn format_validation_error(
     help: &str,
     underline_length: usize,
 ) -> String {
-    format_error_impl(code, file_path, error_kind, line_num, col_num, message, help, underline_length)
+    let mut out = String::new();
+    write_validation_error(
+        &mut out,
+        code,
+        file_path,
+        error_kind,
+        line_num,
+        col_num,
+        message,
+        help,
+        underline_length,
+    )
+    .expect("writing to a String cannot fail");
+    out
+}
+
+/// Write a formatted validation error into an existing buffer
+///
+/// Takes the same arguments as [`format_validation_error`] after the output
+/// buffer `w`, and appends exactly the text that function would return. This
+/// lets callers reuse one `String` when formatting many errors.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::write_validation_error;
+///
+/// let mut buffer = String::new();
+/// write_validation_error(
+///     &mut buffer,
+///     "import { console } from 'tana/invalid';",
+///     "contract.ts",
+///     "Invalid Import",
+///     1,
+///     26,
+///     "Module 'tana/invalid' not found",
+///     "Available modules: tana/core, tana/kv",
+///     12,
+/// )
+/// .unwrap();
+///
+/// assert!(buffer.contains("contract.ts:1:26"));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn write_validation_error<W: std::fmt::Write>(
+    w: &mut W,
+    code: &str,
+    file_path: &str,
+    error_kind: &str,
+    line_num: usize,
+    col_num: usize,
+    message: &str,
+    help: &str,
+    underline_length: usize,
+) -> std::fmt::Result {
+    Diagnostic::builder()
+        .source(code)
+        .file(file_path)
+        .kind(ErrorKind::from(error_kind))
+        .span(Span::single_line(line_num, col_num, underline_length))
+        .message(message.to_string())
+        .help(help.to_string())
+        .build()
+        .write_to(w)
+}
+
+/// Write a formatted validation error as UTF-8 bytes
+///
+/// The byte-sink counterpart of [`write_validation_error`] for callers
+/// holding a [`std::io::Write`], such as a log transport or a file. The
+/// rendered text is streamed into `w` as it is produced, without building
+/// an intermediate `String`, and is byte for byte what
+/// [`format_validation_error`] returns. Errors from `w` are passed back.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::write_validation_error_bytes;
+///
+/// let mut bytes = Vec::new();
+/// write_validation_error_bytes(
+///     &mut bytes,
+///     "import { console } from 'tana/invalid';",
+///     "contract.ts",
+///     "Invalid Import",
+///     1,
+///     26,
+///     "Module 'tana/invalid' not found",
+///     "Available modules: tana/core, tana/kv",
+///     12,
+/// )
+/// .unwrap();
+///
+/// assert!(String::from_utf8(bytes).unwrap().contains("contract.ts:1:26"));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn write_validation_error_bytes<W: std::io::Write>(
+    w: &mut W,
+    code: &str,
+    file_path: &str,
+    error_kind: &str,
+    line_num: usize,
+    col_num: usize,
+    message: &str,
+    help: &str,
+    underline_length: usize,
+) -> std::io::Result<()> {
+    let diagnostic = Diagnostic::builder()
+        .source(code)
+        .file(file_path)
+        .kind(ErrorKind::from(error_kind))
+        .span(Span::single_line(line_num, col_num, underline_length))
+        .message(message.to_string())
+        .help(help.to_string())
+        .build();
+    write!(w, "{}", diagnostic)
 }
 
-/// Internal implementation of error formatting
-/// Used by both WASM binding and native Rust code
-fn format_error_impl(
+/// Format a validation error with custom presentation settings
+///
+/// Identical to [`format_validation_error`] except that layout details such as
+/// the tab width come from `options`.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_validation_error_with_options, FormatOptions};
+///
+/// let options = FormatOptions {
+///     tab_width: 2,
+///     ..FormatOptions::default()
+/// };
+/// let error = format_validation_error_with_options(
+///     "\tlet x = 1;",
+///     "contract.ts",
+///     "Type Error",
+///     1,
+///     6,
+///     "Unexpected binding",
+///     "Remove it",
+///     1,
+///     &options,
+/// );
+///
+/// assert!(error.contains("  let x = 1;"));
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn format_validation_error_with_options(
     code: &str,
     file_path: &str,
     error_kind: &str,
@@ -78,42 +283,184 @@ fn format_error_impl(
     message: &str,
     help: &str,
     underline_length: usize,
+    options: &FormatOptions,
 ) -> String {
-    // Get the problematic line
-    let lines: Vec<&str> = code.lines().collect();
-    let error_line = if line_num > 0 && line_num <= lines.len() {
-        lines[line_num - 1]
-    } else {
-        ""
-    };
-
-    // Ensure underline length is at least 1
-    let underline_length = underline_length.max(1);
-
-    // Build the error message with consistent formatting
-    format!(
-        "\nValidation Error\n\
-        ❌ {}\n\
-        \n\
-        ┌─ {}:{}:{}\n\
-        │\n\
-        {:>3} │ {}\n\
-            │ {}{} {}\n\
-        │\n\
-        = help: {}\n\
-        │\n\
-        └─\n",
-        error_kind,
-        file_path,
-        line_num,
-        col_num,
-        line_num,
-        error_line,
-        " ".repeat(col_num.saturating_sub(1)),
-        "^".repeat(underline_length),
-        message,
-        help
-    )
+    Diagnostic::builder()
+        .source(code)
+        .file(file_path)
+        .kind(ErrorKind::from(error_kind))
+        .line(line_num)
+        .col(col_num)
+        .underline(underline_length)
+        .message(message.to_string())
+        .help(help.to_string())
+        .options(options.clone())
+        .build()
+        .render()
+}
+
+/// Format a validation error with ANSI colors for terminal output
+///
+/// Takes the same arguments as [`format_validation_error`] and produces the
+/// same layout, with the icon and error kind in red, the `= help:` label in
+/// cyan, the gutter dimmed, and the carets in bright red.
+///
+/// The result is a `String` that may be printed anywhere, so whether stdout
+/// is a terminal isn't considered: color is always on unless `NO_COLOR` is
+/// set, which falls back to the plain output of [`format_validation_error`].
+/// Check the stream you write to yourself before choosing this function.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::format_validation_error_colored;
+///
+/// let error = format_validation_error_colored(
+///     "import { console } from 'tana/invalid';",
+///     "contract.ts",
+///     "Invalid Import",
+///     1,
+///     26,
+///     "Module 'tana/invalid' not found",
+///     "Available modules: tana/core, tana/kv",
+///     12,
+/// );
+///
+/// eprintln!("{}", error);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn format_validation_error_colored(
+    code: &str,
+    file_path: &str,
+    error_kind: &str,
+    line_num: usize,
+    col_num: usize,
+    message: &str,
+    help: &str,
+    underline_length: usize,
+) -> String {
+    Diagnostic::builder()
+        .source(code)
+        .file(file_path)
+        .kind(ErrorKind::from(error_kind))
+        .line(line_num)
+        .col(col_num)
+        .underline(underline_length)
+        .message(message.to_string())
+        .help(help.to_string())
+        .color(if ColorChoice::Auto.should_color_on(true) {
+            ColorChoice::Always
+        } else {
+            ColorChoice::Never
+        })
+        .build()
+        .render()
+}
+
+/// Format a validation error as HTML for the web playground
+///
+/// Takes the same arguments as [`format_validation_error`] and produces the
+/// same layout inside `<div class="tana-diag"><pre>…</pre></div>`. The source
+/// line, carets, and help are wrapped in `<span>`s with the classes `source`,
+/// `caret`, and `help` so they can be styled with CSS, and every piece of
+/// text passed in is HTML-escaped.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::format_validation_error_html;
+///
+/// let html = format_validation_error_html(
+///     "import { console } from 'tana/invalid';",
+///     "contract.ts",
+///     "Invalid Import",
+///     1,
+///     26,
+///     "Module 'tana/invalid' not found",
+///     "Available modules: tana/core, tana/kv",
+///     12,
+/// );
+///
+/// assert!(html.contains(r#"<span class="caret">^^^^^^^^^^^^</span>"#));
+/// ```
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn format_validation_error_html(
+    code: &str,
+    file_path: &str,
+    error_kind: &str,
+    line_num: usize,
+    col_num: usize,
+    message: &str,
+    help: &str,
+    underline_length: usize,
+) -> String {
+    let diagnostic = Diagnostic::builder()
+        .source(code)
+        .file(file_path)
+        .kind(ErrorKind::from(error_kind))
+        .line(line_num)
+        .col(col_num)
+        .underline(underline_length)
+        .message(message.to_string())
+        .help(help.to_string())
+        .build();
+    output::to_html(&diagnostic)
+}
+
+/// Format a validation error whose span covers several lines
+///
+/// Every line from `start_line` to `end_line` is printed with its own gutter
+/// number. The first line is underlined from `start_col` to its end, interior
+/// lines are underlined in full, and the last line is underlined up to (but
+/// not including) `end_col`. The message follows the final underline.
+///
+/// When `start_line == end_line` the output is identical to
+/// [`format_validation_error`] with an underline running from `start_col` to
+/// `end_col`.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::format_validation_error_span;
+///
+/// let error = format_validation_error_span(
+///     "export function get() {\n  return {\n}",
+///     "contract.ts",
+///     "Syntax Error",
+///     2,
+///     10,
+///     3,
+///     2,
+///     "Unterminated object literal",
+///     "Add a closing '}'",
+/// );
+///
+/// assert!(error.contains("contract.ts:2:10"));
+/// assert!(error.contains("  3 │ }"));
+/// ```
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn format_validation_error_span(
+    code: &str,
+    file_path: &str,
+    error_kind: &str,
+    start_line: usize,
+    start_col: usize,
+    end_line: usize,
+    end_col: usize,
+    message: &str,
+    help: &str,
+) -> String {
+    Diagnostic::builder()
+        .source(code)
+        .file(file_path)
+        .kind(ErrorKind::from(error_kind))
+        .span(Span::range(start_line, start_col, end_line, end_col))
+        .message(message.to_string())
+        .help(help.to_string())
+        .build()
+        .render()
 }
 
 #[cfg(test)]
@@ -177,6 +524,16 @@ mod tests {
         assert!(error.contains("^")); // At least one caret
     }
 
+    #[test]
+    fn test_zero_column_is_column_one() {
+        let error =
+            format_validation_error("let x = 1;", "test.ts", "Error", 1, 0, "msg", "help", 3);
+
+        assert!(error.contains("┌─ test.ts:1:1\n"));
+        assert!(error.contains("  1 │ let x = 1;\n    │ ^^^ msg\n"));
+        assert_eq!(Diagnostic::builder().col(0).build().span.col, 1);
+    }
+
     #[test]
     fn test_out_of_bounds_line() {
         let error = format_validation_error(
@@ -194,4 +551,393 @@ mod tests {
         assert!(error.contains("❌ Error"));
         assert!(error.contains("999 │")); // Shows requested line number
     }
+
+    /// Display column of `needle` after the gutter `│ ` on the row containing it
+    fn column_after_gutter(row: &str, needle: &str) -> usize {
+        use unicode_width::UnicodeWidthStr;
+
+        let start = row.find("│ ").expect("row has a gutter") + "│ ".len();
+        let offset = row[start..].find(needle).expect("needle on row");
+        row[start..start + offset].width()
+    }
+
+    fn caret_row(error: &str) -> &str {
+        error.lines().find(|l| l.contains('^')).expect("caret row")
+    }
+
+    #[test]
+    fn test_caret_alignment_after_cjk() {
+        let code = "let 名前 = bad;";
+        // "let 名前 = " is 9 characters, so `bad` starts at column 10
+        let error = format_validation_error(code, "cjk.ts", "Type Error", 1, 10, "msg", "help", 3);
+
+        let source_row = error.lines().find(|l| l.contains("名前")).unwrap();
+        let expected = column_after_gutter(source_row, "bad");
+
+        assert_eq!(expected, 11); // 名前 occupies four cells
+        assert_eq!(column_after_gutter(caret_row(&error), "^^^"), expected);
+    }
+
+    #[test]
+    fn test_caret_alignment_after_emoji() {
+        let code = "const 🚀 = launch();";
+        // "const 🚀 = " is 10 characters, so `launch` starts at column 11
+        let error =
+            format_validation_error(code, "emoji.ts", "Type Error", 1, 11, "msg", "help", 6);
+
+        let source_row = error.lines().find(|l| l.contains("🚀")).unwrap();
+        let expected = column_after_gutter(source_row, "launch");
+
+        assert_eq!(expected, 11); // 🚀 occupies two cells
+        assert_eq!(column_after_gutter(caret_row(&error), "^^^^^^ "), expected);
+    }
+
+    #[test]
+    fn test_caret_alignment_after_zwj_emoji() {
+        // The family is five scalar values but one two-cell character
+        let code = "const 👨\u{200d}👩\u{200d}👧 = launch();";
+        let error =
+            format_validation_error(code, "emoji.ts", "Type Error", 1, 15, "msg", "help", 6);
+
+        let source_row = error.lines().find(|l| l.contains("👨")).unwrap();
+        let expected = column_after_gutter(source_row, "launch");
+
+        assert_eq!(expected, 11);
+        assert_eq!(column_after_gutter(caret_row(&error), "^^^^^^ "), expected);
+    }
+
+    #[test]
+    fn test_underline_length_is_display_columns() {
+        let code = "let 名前 = 1;";
+        let error = format_validation_error(code, "cjk.ts", "Type Error", 1, 5, "msg", "help", 4);
+
+        // Two wide characters span four columns, so four carets cover them
+        assert!(caret_row(&error).contains("^^^^ msg"));
+        assert_eq!(column_after_gutter(caret_row(&error), "^"), 4);
+    }
+
+    #[test]
+    fn test_tab_indented_line_aligns_carets() {
+        let code = "fn main() {\n\t\tlet x = oops;\n}";
+        // Two tabs plus "let x = " puts `oops` at column 11
+        let error = format_validation_error(code, "tabs.ts", "Type Error", 2, 11, "msg", "help", 4);
+
+        let source_row = error.lines().find(|l| l.contains("let x")).unwrap();
+        assert!(!source_row.contains('\t'));
+        assert!(source_row.contains("        let x = oops;"));
+        assert_eq!(column_after_gutter(caret_row(&error), "^^^^"), 16);
+        assert_eq!(column_after_gutter(source_row, "oops"), 16);
+    }
+
+    #[test]
+    fn test_mixed_tabs_and_spaces_align_carets() {
+        let code = "  \t x\tbad";
+        let options = FormatOptions {
+            tab_width: 4,
+            ..FormatOptions::default()
+        };
+        // "  \t x\t" is six characters, so `bad` starts at column 7
+        let error = format_validation_error_with_options(
+            code,
+            "mixed.ts",
+            "Type Error",
+            1,
+            7,
+            "msg",
+            "help",
+            3,
+            &options,
+        );
+
+        let source_row = error.lines().find(|l| l.contains("bad")).unwrap();
+        assert_eq!(column_after_gutter(source_row, "bad"), 8);
+        assert_eq!(column_after_gutter(caret_row(&error), "^^^"), 8);
+    }
+
+    #[test]
+    fn test_custom_tab_width() {
+        let options = FormatOptions {
+            tab_width: 8,
+            ..FormatOptions::default()
+        };
+        let error = format_validation_error_with_options(
+            "\tx",
+            "tabs.ts",
+            "Type Error",
+            1,
+            2,
+            "msg",
+            "help",
+            1,
+            &options,
+        );
+
+        assert_eq!(column_after_gutter(caret_row(&error), "^"), 8);
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let crlf = format_validation_error(
+            "line1\r\nbad line\r\nline3",
+            "crlf.ts",
+            "Type Error",
+            2,
+            5,
+            "msg",
+            "help",
+            4,
+        );
+        let lf = format_validation_error(
+            "line1\nbad line\nline3",
+            "crlf.ts",
+            "Type Error",
+            2,
+            5,
+            "msg",
+            "help",
+            4,
+        );
+
+        assert!(!crlf.contains('\r'));
+        assert!(crlf.contains("bad line\n"));
+        assert_eq!(column_after_gutter(caret_row(&crlf), "^^^^"), 4);
+        assert_eq!(crlf, lf);
+    }
+
+    #[test]
+    fn test_stray_trailing_carriage_return() {
+        // `str::lines` leaves a lone trailing `\r` in place
+        let error = format_validation_error(
+            "first\nbad line\r",
+            "crlf.ts",
+            "Type Error",
+            2,
+            1,
+            "msg",
+            "help",
+            3,
+        );
+
+        assert!(!error.contains('\r'));
+        assert!(error.contains("bad line\n"));
+    }
+
+    #[test]
+    fn test_span_single_line_matches_legacy() {
+        let code = "import { console } from 'tana/invalid';";
+        let legacy =
+            format_validation_error(code, "test.ts", "Invalid Import", 1, 26, "msg", "help", 12);
+        let span = format_validation_error_span(
+            code,
+            "test.ts",
+            "Invalid Import",
+            1,
+            26,
+            1,
+            38,
+            "msg",
+            "help",
+        );
+
+        assert_eq!(span, legacy);
+    }
+
+    #[test]
+    fn test_span_across_lines() {
+        let code = "const a = {\n  b: 1,\n  c: 2\nexport";
+        let error = format_validation_error_span(
+            code,
+            "span.ts",
+            "Syntax Error",
+            1,
+            11,
+            3,
+            5,
+            "unterminated",
+            "close it",
+        );
+        let rows: Vec<&str> = error.lines().collect();
+
+        assert!(error.contains("span.ts:1:11"));
+        let first = rows.iter().position(|l| l.starts_with("  1 │")).unwrap();
+        assert_eq!(rows[first + 1], format!("    │ {}^", " ".repeat(10)));
+        assert_eq!(rows[first + 2], "  2 │   b: 1,");
+        assert_eq!(rows[first + 3], "    │ ^^^^^^^");
+        assert_eq!(rows[first + 4], "  3 │   c: 2");
+        assert_eq!(rows[first + 5], "    │ ^^^^ unterminated");
+        assert!(!error.contains("  4 │"));
+    }
+
+    #[test]
+    fn test_span_skips_caret_row_for_blank_interior_line() {
+        let code = "start\n\nend";
+        let error = format_validation_error_span(
+            code,
+            "span.ts",
+            "Syntax Error",
+            1,
+            1,
+            3,
+            4,
+            "msg",
+            "help",
+        );
+        let rows: Vec<&str> = error.lines().collect();
+        let blank = rows.iter().position(|l| *l == "  2 │").unwrap();
+
+        assert_eq!(rows[blank + 1], "  3 │ end");
+        assert_eq!(error.matches('^').count(), 5 + 3);
+    }
+
+    #[test]
+    fn test_colored_output_escapes() {
+        let code = "import { console } from 'tana/invalid';";
+        let colored = Diagnostic::builder()
+            .source(code)
+            .file("test.ts")
+            .kind("Invalid Import")
+            .line(1)
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .color(ColorChoice::Always)
+            .build()
+            .render();
+        let plain = format_validation_error(
+            code,
+            "test.ts",
+            "Invalid Import",
+            1,
+            26,
+            "Module 'tana/invalid' not found",
+            "Available modules: tana/core, tana/kv",
+            12,
+        );
+
+        assert!(colored.contains("\x1b[31m❌ Invalid Import\x1b[0m"));
+        assert!(colored.contains("\x1b[36m= help:\x1b[0m"));
+        assert!(colored.contains("\x1b[2m│\x1b[0m"));
+        assert!(colored.contains("\x1b[91m^^^^^^^^^^^^\x1b[0m"));
+        assert!(!plain.contains('\x1b'));
+
+        // Stripping the escapes leaves exactly the plain rendering
+        let stripped = colored
+            .split("\x1b[")
+            .enumerate()
+            .map(|(i, part)| {
+                if i == 0 {
+                    part
+                } else {
+                    &part[part.find('m').unwrap() + 1..]
+                }
+            })
+            .collect::<String>();
+        assert_eq!(stripped, plain);
+    }
+
+    #[test]
+    fn test_truecolor_escapes_only_under_truecolor() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;")
+            .severity(Severity::Warning)
+            .underline(3)
+            .color(ColorChoice::Always)
+            .build();
+        let with_depth = |color_depth| {
+            diagnostic.render_with(&Theme {
+                color_depth,
+                ..Theme::default()
+            })
+        };
+
+        let truecolor = with_depth(ColorDepth::TrueColor);
+        assert!(truecolor.contains("\x1b[38;2;255;175;0m~~~\x1b[0m"));
+        assert!(!with_depth(ColorDepth::Ansi256).contains("\x1b[38;2;"));
+        assert!(with_depth(ColorDepth::Ansi256).contains("\x1b[38;5;214m~~~"));
+
+        // The default depth keeps the basic escapes
+        let basic = with_depth(ColorDepth::Ansi16);
+        assert!(!basic.contains("\x1b[38;"));
+        assert_eq!(basic, diagnostic.render());
+    }
+
+    #[test]
+    fn test_write_bytes_decode_to_formatted_error() {
+        let code = "const s = '名前'; eval(s);";
+        let mut bytes = Vec::new();
+        write_validation_error_bytes(
+            &mut bytes,
+            code,
+            "contract.ts",
+            "Forbidden Global",
+            1,
+            17,
+            "'eval' is not available in contracts",
+            "remove it",
+            4,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            format_validation_error(
+                code,
+                "contract.ts",
+                "Forbidden Global",
+                1,
+                17,
+                "'eval' is not available in contracts",
+                "remove it",
+                4,
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_two_errors_into_one_buffer() {
+        let mut buffer = String::new();
+        write_validation_error(
+            &mut buffer,
+            "import { kv } from 'tana/kvs';",
+            "first.ts",
+            "Invalid Import",
+            1,
+            21,
+            "Module 'tana/kvs' not found",
+            "Did you mean tana/kv?",
+            8,
+        )
+        .unwrap();
+        let after_first = buffer.len();
+        write_validation_error(
+            &mut buffer,
+            "export function nope() {}",
+            "second.ts",
+            "Invalid Export",
+            1,
+            17,
+            "Function 'nope' is not allowed",
+            "Allowed functions: init, contract, get, post",
+            4,
+        )
+        .unwrap();
+
+        let first = buffer.find("first.ts:1:21").unwrap();
+        let second = buffer.find("second.ts:1:17").unwrap();
+        assert!(first < after_first && after_first < second);
+        assert_eq!(
+            &buffer[..after_first],
+            format_validation_error(
+                "import { kv } from 'tana/kvs';",
+                "first.ts",
+                "Invalid Import",
+                1,
+                21,
+                "Module 'tana/kvs' not found",
+                "Did you mean tana/kv?",
+                8,
+            )
+        );
+    }
 }
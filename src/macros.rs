@@ -0,0 +1,94 @@
+//! Declarative shorthand for building diagnostics
+
+/// Build a [`Diagnostic`](crate::Diagnostic) from keyword-like arguments
+///
+/// The source code and file path come first, followed by the required
+/// `kind`, `line`, `col`, `len` (underline length) and `message`, in that
+/// order. `help` and `note` may follow and are optional. Everything else
+/// keeps its [`DiagnosticBuilder`](crate::DiagnosticBuilder) default.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::diagnostic;
+///
+/// let code = "const x: number = \"one\";";
+/// let d = diagnostic!(code, "types.ts",
+///     kind: "Type Error",
+///     line: 1,
+///     col: 19,
+///     len: 5,
+///     message: "expected number, found string",
+///     help: "remove the quotes",
+/// );
+///
+/// assert_eq!(d.kind, "Type Error");
+/// assert!(d.note.is_none());
+/// ```
+#[macro_export]
+macro_rules! diagnostic {
+    (
+        $code:expr, $file:expr,
+        kind: $kind:expr,
+        line: $line:expr,
+        col: $col:expr,
+        len: $len:expr,
+        message: $message:expr
+        $(, help: $help:expr)?
+        $(, note: $note:expr)?
+        $(,)?
+    ) => {
+        $crate::Diagnostic::builder()
+            .source($code)
+            .file($file)
+            .kind($kind)
+            .line($line)
+            .col($col)
+            .underline($len)
+            .message($message)
+            $(.help($help))?
+            $(.note($note))?
+            .build()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Diagnostic;
+
+    #[test]
+    fn test_macro_matches_builder() {
+        let code = "let total = 1;\ntotal = \"two\";";
+        let from_macro = diagnostic!(code, "contract.ts",
+            kind: "Type Error",
+            line: 2,
+            col: 9,
+            len: 5,
+            message: "expected number, found string",
+            help: "assign a number",
+            note: "declared on line 1",
+        );
+        let from_builder = Diagnostic::builder()
+            .source(code)
+            .file("contract.ts")
+            .kind("Type Error")
+            .line(2)
+            .col(9)
+            .underline(5)
+            .message("expected number, found string")
+            .help("assign a number")
+            .note("declared on line 1")
+            .build();
+
+        assert_eq!(from_macro.render(), from_builder.render());
+    }
+
+    #[test]
+    fn test_macro_without_help_or_note() {
+        let d =
+            diagnostic!("x", "a.ts", kind: "Syntax Error", line: 1, col: 1, len: 1, message: "bad");
+
+        assert!(d.help.is_none() && d.note.is_none());
+        assert_eq!(d.message, "bad");
+    }
+}
@@ -0,0 +1,205 @@
+//! Presentation settings shared by every renderer
+
+use std::path::Path;
+
+/// Tab width used when none is configured
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// Widest a source line may be printed, in display columns, unless configured
+pub const DEFAULT_MAX_LINE_WIDTH: usize = 120;
+
+/// Characters used to draw the box around a snippet
+///
+/// Each style selects a built-in [`Theme`](crate::Theme).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum BoxStyle {
+    /// Unicode box-drawing characters and emoji icons
+    #[default]
+    Unicode,
+    /// Plain ASCII (`+-`, `|`, `[error]`) for sinks that mangle Unicode
+    Ascii,
+}
+
+/// Where a clickable location link opens, if anywhere
+///
+/// Links use the OSC 8 escape understood by most modern terminals; others
+/// print the location as plain text. A link needs an absolute path, so a
+/// relative one is resolved against the `workspace_root` option and left
+/// unlinked when there is no root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum Hyperlinks {
+    /// Print the location as plain text
+    #[default]
+    Off,
+    /// Link to the file with a `file://` URI
+    File,
+    /// Link to the exact line and column with a `vscode://file/` URI
+    Vscode,
+}
+
+impl Hyperlinks {
+    /// URI the location of `path:line:col` links to, or `None` when links are
+    /// off or `path` can't be made absolute with `workspace_root`
+    pub(crate) fn uri(
+        self,
+        path: &str,
+        workspace_root: Option<&str>,
+        line: usize,
+        col: usize,
+    ) -> Option<String> {
+        if self == Hyperlinks::Off {
+            return None;
+        }
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            let root = workspace_root.filter(|root| root.starts_with('/'))?;
+            Path::new(root).join(path).to_str()?.to_string()
+        };
+        let path = crate::output::encode_path(&path);
+        match self {
+            Hyperlinks::Off => None,
+            Hyperlinks::File => Some(format!("file://{}", path)),
+            Hyperlinks::Vscode => Some(format!("vscode://file{}:{}:{}", path, line, col)),
+        }
+    }
+}
+
+/// Where a message goes relative to its underline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum LabelPlacement {
+    /// On the underline's row, after the carets
+    #[default]
+    Inline,
+    /// On the rows below the underline, starting under its first caret, so
+    /// a long message keeps the full width
+    Below,
+    /// Inline when the message fits after the carets within the render
+    /// width, measured in display columns, and below otherwise; always
+    /// inline when no render width is set
+    Auto,
+}
+
+/// Presentation settings for the error formatter
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct FormatOptions {
+    /// Number of columns between tab stops when expanding tabs in source lines
+    pub tab_width: usize,
+    /// Number of source lines to show above and below the error line
+    pub context_lines: usize,
+    /// Whether to show the nearest non-blank line above the error line
+    ///
+    /// A lighter alternative to `context_lines` for errors inside nested
+    /// blocks: the enclosing statement shows the indentation the error
+    /// line sits at. Blank lines in between are folded away.
+    pub show_prev_line: bool,
+    /// Widest a source line may be printed, in display columns
+    ///
+    /// Longer lines are cut around the error column with `…` markers.
+    /// `None` prints every line in full.
+    pub max_line_width: Option<usize>,
+    /// Characters used to draw the box
+    pub box_style: BoxStyle,
+    /// Widest a help row may be, in display columns, before it is word-wrapped
+    ///
+    /// Continuation lines stay indented under the text after `= help:`.
+    /// `None` leaves help lines as given.
+    pub wrap_width: Option<usize>,
+    /// Whether the location line is a clickable terminal link
+    pub hyperlinks: Hyperlinks,
+    /// Directory that displayed file paths are made relative to
+    ///
+    /// A path under the root shows as e.g. `contracts/foo.ts` in the
+    /// location line, keeping local directories out of shared logs. Paths
+    /// outside it, and every path when `None`, are shown in full.
+    pub workspace_root: Option<String>,
+    /// Widest the whole box may be, in display columns
+    ///
+    /// Source lines are cut and messages and help word-wrapped so every row
+    /// fits, on top of any narrower `max_line_width` or `wrap_width`. `None`
+    /// sets no overall limit.
+    pub render_width: Option<usize>,
+    /// Whether text containing right-to-left scripts is wrapped in Unicode
+    /// directional isolates
+    ///
+    /// Arabic or Hebrew in a source line or message can otherwise be
+    /// reordered by the terminal together with the box around it. Isolated
+    /// text still reads correctly, while the gutter and carets stay in
+    /// left-to-right order. Off by default, so output is unchanged for
+    /// callers that don't need it.
+    pub isolate_bidi: bool,
+    /// Whether each message is followed by the column it points at, as in
+    /// `(col 26)`, so it needn't be counted by eye
+    ///
+    /// Spans covering several lines show their end column too.
+    pub show_columns: bool,
+    /// Number added to every displayed line number
+    ///
+    /// When the source is a snippet embedded in a larger document, such as
+    /// a markdown code fence starting on document line 41, an offset of 40
+    /// shows the document's line numbers in the gutter and location line.
+    /// Lines are still looked up in the snippet by their own numbers.
+    pub line_offset: usize,
+    /// Where each message goes relative to its underline
+    pub label_placement: LabelPlacement,
+    /// Whether to leave out the blank line before the title and the `│`
+    /// separator row above the bottom corner
+    ///
+    /// A trimmed box is a tight block for embedding in output that manages
+    /// its own spacing. Off by default.
+    pub trim_surrounding: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            tab_width: DEFAULT_TAB_WIDTH,
+            context_lines: 0,
+            show_prev_line: false,
+            max_line_width: Some(DEFAULT_MAX_LINE_WIDTH),
+            box_style: BoxStyle::Unicode,
+            wrap_width: None,
+            hyperlinks: Hyperlinks::Off,
+            workspace_root: None,
+            render_width: None,
+            isolate_bidi: false,
+            show_columns: false,
+            line_offset: 0,
+            label_placement: LabelPlacement::Inline,
+            trim_surrounding: false,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// `path` as displayed: relative to the workspace root when under it
+    pub(crate) fn display_path<'a>(&self, path: &'a str) -> &'a str {
+        let Some(root) = &self.workspace_root else {
+            return path;
+        };
+        match Path::new(path).strip_prefix(root).map(Path::to_str) {
+            Ok(Some(relative)) if !relative.is_empty() => relative,
+            _ => path,
+        }
+    }
+}
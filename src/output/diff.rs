@@ -0,0 +1,100 @@
+//! Before-and-after views of suggested fixes
+
+use crate::{render, Diagnostic};
+
+/// Show the line a diagnostic's suggested fix changes, before and after
+///
+/// When the diagnostic carries a [`Suggestion`](crate::Suggestion), the
+/// output starts with its `file:line:col` location and
+/// [`format_inline`](crate::format_inline) summary, followed by a mini-diff
+/// of the line: `- 1 │ ...` with the current text and `+ 1 │ ...` with the
+/// replacement applied, in red and green when the diagnostic's color choice
+/// allows. Line numbers follow the `line_offset` option, and tabs are
+/// expanded as in the error box. Without a suggestion, or when the
+/// suggestion's line isn't in the source, the result is the normal
+/// [`Diagnostic::render`] box.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_fix_diff, Diagnostic, Suggestion};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .source("import { x } from 'tana/invalid';")
+///     .file("contract.ts")
+///     .kind("Invalid Import")
+///     .col(20)
+///     .underline(12)
+///     .message("Module 'tana/invalid' not found")
+///     .suggestion(Suggestion::new(1, 20, 12, "tana/kv"))
+///     .build();
+///
+/// assert_eq!(
+///     format_fix_diff(&diagnostic),
+///     "contract.ts:1:20: ❌ Invalid Import: Module 'tana/invalid' not found\n\
+///      - 1 │ import { x } from 'tana/invalid';\n\
+///      + 1 │ import { x } from 'tana/kv';\n"
+/// );
+/// ```
+pub fn format_fix_diff(diagnostic: &Diagnostic) -> String {
+    render::fix_diff(diagnostic, &diagnostic.options.box_style.theme())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorChoice, Suggestion};
+
+    #[test]
+    fn test_diff_shows_line_before_and_after_fix() {
+        let diagnostic = Diagnostic::builder()
+            .source("let a = 1;\nimport { kv } from 'tana/kvs';")
+            .kind("Invalid Import")
+            .line(2)
+            .col(21)
+            .underline(8)
+            .message("Module 'tana/kvs' not found")
+            .help("did you mean 'tana/kv'?")
+            .suggestion(Suggestion::new(2, 21, 8, "tana/kv"));
+        let diff = format_fix_diff(&diagnostic.clone().file("a.ts").line_offset(40).build());
+
+        assert!(diff.starts_with("a.ts:42:21: ❌ Invalid Import: Module 'tana/kvs' not found"));
+        let rows: Vec<&str> = diff.lines().skip(1).collect();
+        assert_eq!(
+            rows,
+            [
+                "- 42 │ import { kv } from 'tana/kvs';",
+                "+ 42 │ import { kv } from 'tana/kv';"
+            ]
+        );
+
+        let colored = format_fix_diff(&diagnostic.color(ColorChoice::Always).build());
+        assert!(colored.contains("\x1b[31m- 2 │ import { kv } from 'tana/kvs';\x1b[0m\n"));
+        assert!(colored.contains("\x1b[32m+ 2 │ import { kv } from 'tana/kv';\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_without_suggestion_renders_box() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;")
+            .message("msg")
+            .build();
+
+        assert_eq!(format_fix_diff(&diagnostic), diagnostic.render());
+    }
+
+    #[test]
+    fn test_suggestion_on_missing_line_renders_box() {
+        let at = |line| {
+            Diagnostic::builder()
+                .source("let x = 1;\nlet y = 2;")
+                .message("msg")
+                .suggestion(Suggestion::new(line, 1, 1, "z"))
+                .build()
+        };
+
+        for diagnostic in [at(50), at(0)] {
+            assert_eq!(format_fix_diff(&diagnostic), diagnostic.render());
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! GitHub Actions workflow commands, shown as annotations on pull request diffs
+
+use crate::{Diagnostic, Severity};
+
+/// Encode a diagnostic as a GitHub Actions annotation command
+///
+/// Produces `::error file={path},line={line},col={col}::{message}`, using
+/// `::warning` for warnings and `::notice` for info and hints. Annotations
+/// are a single line, so the help text is appended to the message after
+/// ` - help: `, and newlines are percent-encoded.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{to_github_annotation, Diagnostic};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .file("contract.ts")
+///     .line(1)
+///     .col(26)
+///     .message("Module 'tana/invalid' not found")
+///     .help("Available modules: tana/core, tana/kv")
+///     .build();
+///
+/// assert_eq!(
+///     to_github_annotation(&diagnostic),
+///     "::error file=contract.ts,line=1,col=26::Module 'tana/invalid' not found - help: Available modules: tana/core, tana/kv"
+/// );
+/// ```
+pub fn to_github_annotation(diagnostic: &Diagnostic) -> String {
+    let command = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Hint => "notice",
+    };
+
+    let mut message = diagnostic.message.to_string();
+    if let Some(help) = diagnostic.help.as_deref().filter(|help| !help.is_empty()) {
+        message.push_str(" - help: ");
+        message.push_str(help);
+    }
+
+    format!(
+        "::{} file={},line={},col={}::{}",
+        command,
+        escape_property(&diagnostic.file_path),
+        diagnostic.span.line,
+        diagnostic.span.col,
+        escape_data(&message)
+    )
+}
+
+/// Percent-encode the characters that would end a command's message
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Percent-encode a property value, which also can't contain `:` or `,`
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation_encodes_percent_and_newline() {
+        let diagnostic = Diagnostic::builder()
+            .file("src/contract.ts")
+            .line(3)
+            .col(7)
+            .message("100% of\r\nthe budget")
+            .help("lower it")
+            .build();
+
+        assert_eq!(
+            to_github_annotation(&diagnostic),
+            "::error file=src/contract.ts,line=3,col=7::100%25 of%0D%0Athe budget - help: lower it"
+        );
+    }
+
+    #[test]
+    fn test_annotation_commands_by_severity() {
+        let annotate = |severity| {
+            let diagnostic = Diagnostic::builder()
+                .file("a,b:c.ts")
+                .message("msg")
+                .severity(severity)
+                .build();
+            to_github_annotation(&diagnostic)
+        };
+
+        assert_eq!(
+            annotate(Severity::Warning),
+            "::warning file=a%2Cb%3Ac.ts,line=1,col=1::msg"
+        );
+        assert!(annotate(Severity::Error).starts_with("::error "));
+        assert!(annotate(Severity::Hint).starts_with("::notice "));
+    }
+}
@@ -0,0 +1,187 @@
+//! Semantic HTML for the web playground
+
+use std::fmt::Write;
+
+use crate::render::{self, Frame, SnippetRow};
+use crate::text;
+use crate::Diagnostic;
+
+/// Render a diagnostic as HTML with the same layout as the text box
+///
+/// The box is a `<pre>` inside `<div class="tana-diag">`. Source lines are
+/// wrapped in `<span class="source">`, underlines in `<span class="caret">`
+/// (with an extra `secondary` class for `~~~` labels), the help and note in
+/// `<span class="help">` and `<span class="note">`, related locations in
+/// `<span class="related">`, and a docs link in `<span class="see">`, so a
+/// stylesheet can color each part. All text from
+/// the diagnostic is HTML-escaped.
+pub(crate) fn to_html(diagnostic: &Diagnostic) -> String {
+    let theme = diagnostic.options.box_style.theme();
+    let (frame, rows) = render::layout(diagnostic, &theme);
+    let mut out = String::new();
+    write_html(&mut out, &frame, &rows).expect("writing to a String cannot fail");
+    out
+}
+
+fn write_html(out: &mut String, frame: &Frame, rows: &[SnippetRow]) -> std::fmt::Result {
+    let width = render::gutter_width(rows);
+    let blank = "";
+    let theme = frame.theme;
+    let bar = theme.gutter_char;
+    let separator = theme.empty_gutter();
+
+    write!(
+        out,
+        "<div class=\"tana-diag\" data-severity=\"{}\"><pre>\
+        <span class=\"title\">{}</span>\n\
+        <span class=\"kind\">{} {}{}</span>\n\
+        \n\
+        {blank:width$} {} <span class=\"location\">{}:{}:{}</span>\n\
+        {blank:width$} {separator}\n",
+        frame.severity.name(),
+        theme.title(frame.severity),
+        theme.icon(frame.severity),
+        escape(frame.kind),
+        frame
+            .code
+            .map_or(String::new(), |code| format!(" [{}]", escape(code))),
+        theme.top,
+        escape(frame.file_path.unwrap_or_default()),
+        frame.line_num,
+        frame.col_num,
+    )?;
+
+    for (index, row) in rows.iter().enumerate() {
+        if index > 0 && row.line_num > rows[index - 1].line_num + 1 {
+            writeln!(out, "{:>width$} {bar}", escape(theme.fold))?;
+        }
+        writeln!(
+            out,
+            "{:>width$} {bar} <span class=\"source\">{}</span>",
+            row.line_num,
+            escape(&row.text)
+        )?;
+
+        for mark in &row.marks {
+            let (class, glyph) = if mark.primary {
+                ("caret", theme.caret(mark.severity))
+            } else {
+                ("caret secondary", theme.secondary_caret_char)
+            };
+            write!(
+                out,
+                "{blank:width$} {bar} {blank:padding$}<span class=\"{}\">{}</span>",
+                class,
+                glyph.to_string().repeat(mark.underline),
+                padding = mark.padding,
+            )?;
+            if let Some(label) = mark.label.as_deref().filter(|label| !label.is_empty()) {
+                let gap = if mark.underline > 0 { " " } else { "" };
+                write!(out, "{gap}<span class=\"message\">{}</span>", escape(label))?;
+            }
+            writeln!(out)?;
+        }
+    }
+
+    writeln!(out, "{blank:width$} {separator}")?;
+    let footnotes = [
+        ("help", theme.help_label(), frame.help.as_deref()),
+        ("note", theme.note_label(), frame.note.as_deref()),
+    ];
+    for (class, label, body) in footnotes {
+        let Some(body) = body.filter(|body| !body.is_empty()) else {
+            continue;
+        };
+        let indent = width + 1 + text::width(&label) + 1;
+        writeln!(
+            out,
+            "{blank:width$} <span class=\"{}\">{} {}</span>",
+            class,
+            escape(&label),
+            escape(body).replace('\n', &format!("\n{blank:indent$}"))
+        )?;
+        writeln!(out, "{blank:width$} {separator}")?;
+    }
+    if !frame.related.is_empty() {
+        for location in &frame.related {
+            writeln!(
+                out,
+                "{blank:width$} <span class=\"related\">{} {}</span>",
+                escape(&theme.related_label()),
+                escape(location)
+            )?;
+        }
+        writeln!(out, "{blank:width$} {separator}")?;
+    }
+    if let Some(url) = frame.docs_url.filter(|url| !url.is_empty()) {
+        writeln!(
+            out,
+            "{blank:width$} <span class=\"see\">{} <a href=\"{}\">{}</a></span>",
+            escape(&theme.see_label()),
+            escape(url),
+            escape(url)
+        )?;
+        writeln!(out, "{blank:width$} {separator}")?;
+    }
+    write!(out, "{blank:width$} {}</pre></div>", theme.bottom)
+}
+
+/// Escape the characters HTML treats as markup
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_escapes_user_text() {
+        let diagnostic = Diagnostic::builder()
+            .source("const s = \"<b>\";")
+            .file("<contract>.ts")
+            .kind("Type <Error>")
+            .col(11)
+            .underline(5)
+            .message("<script>alert(1)</script>")
+            .help("use & not &&")
+            .build();
+        let html = to_html(&diagnostic);
+
+        assert!(!html.contains("<script>"));
+        assert!(
+            html.contains("<span class=\"message\">&lt;script&gt;alert(1)&lt;/script&gt;</span>")
+        );
+        assert!(html.contains("<span class=\"source\">const s = &quot;&lt;b&gt;&quot;;</span>"));
+        assert!(html.contains("<span class=\"location\">&lt;contract&gt;.ts:1:11</span>"));
+        assert!(html.contains("<span class=\"help\">= help: use &amp; not &amp;&amp;</span>"));
+    }
+
+    #[test]
+    fn test_html_structure() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;")
+            .col(5)
+            .message("msg")
+            .build();
+        let html = to_html(&diagnostic);
+
+        assert!(html.starts_with("<div class=\"tana-diag\" data-severity=\"error\"><pre>"));
+        assert!(html.ends_with("\n    └─</pre></div>"));
+        assert!(html.contains(
+            "    │     <span class=\"caret\">^</span> <span class=\"message\">msg</span>\n"
+        ));
+        assert!(!html.contains("class=\"help\""));
+    }
+}
@@ -0,0 +1,227 @@
+//! Language Server Protocol diagnostics
+
+use super::{file_uri, json};
+use crate::{position, Diagnostic, Severity};
+
+/// Encode a diagnostic as an LSP `Diagnostic` JSON object
+///
+/// Positions are converted from our 1-indexed line and column to the
+/// zero-indexed `{line, character}` pairs LSP expects, with the end of the
+/// range placed after the characters the underline covers on the
+/// diagnostic's line of `source`, or at the end of its
+/// [`Span::range`](crate::Span::range). The help
+/// text, when present, is carried in `relatedInformation` pointing at the
+/// same range, and the error code, when set, in `code`.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{to_lsp_json, Diagnostic};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .file("contract.ts")
+///     .line(1)
+///     .col(26)
+///     .underline(12)
+///     .message("Module 'tana/invalid' not found")
+///     .build();
+///
+/// let json = to_lsp_json(&diagnostic);
+/// assert!(json.contains(r#""start":{"line":0,"character":25}"#));
+/// assert!(json.contains(r#""end":{"line":0,"character":37}"#));
+/// ```
+pub fn to_lsp_json(diagnostic: &Diagnostic) -> String {
+    let (end_line, end) = diagnostic.end_position();
+    encode(diagnostic, diagnostic.span.col, end_line, end)
+}
+
+/// Encode a diagnostic as an LSP `Diagnostic` whose characters are UTF-16
+/// code units
+///
+/// The same object as [`to_lsp_json`], except that each `character` is
+/// recomputed from the line of `code` it falls on to count UTF-16 code
+/// units, as the LSP specification requires. A character outside the Basic
+/// Multilingual Plane, such as most emoji, counts as two units, so columns
+/// after one shift right in the editor.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{to_lsp_utf16, Diagnostic};
+///
+/// let code = "log('🚀'); eval(x);";
+/// let diagnostic = Diagnostic::builder().line(1).col(11).underline(4).build();
+///
+/// let json = to_lsp_utf16(&diagnostic, code);
+/// assert!(json.contains(r#""start":{"line":0,"character":11}"#));
+/// assert!(json.contains(r#""end":{"line":0,"character":15}"#));
+/// ```
+pub fn to_lsp_utf16(diagnostic: &Diagnostic, code: &str) -> String {
+    let (end_line, end) = diagnostic.end_position_in(code);
+    encode(
+        diagnostic,
+        position::utf16_col(code, diagnostic.span.line, diagnostic.span.col),
+        end_line,
+        position::utf16_col(code, end_line, end),
+    )
+}
+
+/// The LSP object for `diagnostic` with its range running from its line at
+/// `col` to `end_line` at `end`, all 1-indexed
+fn encode(diagnostic: &Diagnostic, col: usize, end_line: usize, end: usize) -> String {
+    let line = diagnostic.span.line.saturating_sub(1);
+    let start = col.saturating_sub(1);
+    let (end_line, end) = (end_line.saturating_sub(1), end.saturating_sub(1));
+    let range = format!(
+        r#"{{"start":{{"line":{line},"character":{start}}},"end":{{"line":{end_line},"character":{end}}}}}"#
+    );
+
+    let mut out = format!(
+        r#"{{"range":{},"severity":{},"source":"tana-validation","message":{}"#,
+        range,
+        lsp_severity(diagnostic.severity),
+        json::string(&diagnostic.message)
+    );
+
+    if let Some(code) = &diagnostic.code {
+        out.push_str(&format!(r#","code":{}"#, json::string(code)));
+    }
+
+    if let Some(help) = diagnostic.help.as_deref().filter(|help| !help.is_empty()) {
+        out.push_str(&format!(
+            r#","relatedInformation":[{{"location":{{"uri":{},"range":{}}},"message":{}}}]"#,
+            json::string(&file_uri(&diagnostic.file_path)),
+            range,
+            json::string(help)
+        ));
+    }
+
+    out.push('}');
+    out
+}
+
+/// LSP `DiagnosticSeverity` number for a severity
+fn lsp_severity(severity: Severity) -> u8 {
+    match severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Info => 3,
+        Severity::Hint => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_lsp_range_round_trips_to_source_bytes() {
+        let code = "import { console } from 'tana/invalid';";
+        let diagnostic = Diagnostic::builder()
+            .source(code)
+            .file("/work/contract.ts")
+            .kind("Invalid Import")
+            .line(1)
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .build();
+
+        let value: Value = serde_json::from_str(&to_lsp_json(&diagnostic)).unwrap();
+        let range = &value["range"];
+        let start = range["start"]["character"].as_u64().unwrap() as usize;
+        let end = range["end"]["character"].as_u64().unwrap() as usize;
+
+        assert_eq!(range["start"]["line"], 0);
+        assert_eq!(range["end"]["line"], 0);
+        assert_eq!(&code[start..end], "tana/invalid");
+        assert_eq!(value["severity"], 1);
+        assert_eq!(value["message"], "Module 'tana/invalid' not found");
+
+        let related = &value["relatedInformation"][0];
+        assert_eq!(related["message"], "Available modules: tana/core, tana/kv");
+        assert_eq!(related["location"]["uri"], "file:///work/contract.ts");
+        assert_eq!(related["location"]["range"], *range);
+    }
+
+    #[test]
+    fn test_lsp_range_ends_where_span_ends() {
+        let diagnostic = Diagnostic::builder()
+            .span(crate::Span::range(2, 11, 4, 2))
+            .message("unterminated object")
+            .build();
+
+        let value: Value = serde_json::from_str(&to_lsp_json(&diagnostic)).unwrap();
+
+        assert_eq!(value["range"]["start"]["line"], 1);
+        assert_eq!(value["range"]["start"]["character"], 10);
+        assert_eq!(value["range"]["end"]["line"], 3);
+        assert_eq!(value["range"]["end"]["character"], 1);
+    }
+
+    #[test]
+    fn test_utf16_characters_count_astral_emoji_twice() {
+        let code = "const s = '😀'; eval(s);";
+        let diagnostic = Diagnostic::builder()
+            .source(code)
+            .line(1)
+            .col(16)
+            .underline(4)
+            .message("'eval' is not available in contracts")
+            .help("remove it")
+            .build();
+
+        let scalar: Value = serde_json::from_str(&to_lsp_json(&diagnostic)).unwrap();
+        let utf16: Value = serde_json::from_str(&to_lsp_utf16(&diagnostic, code)).unwrap();
+
+        assert_eq!(scalar["range"]["start"]["character"], 15);
+        assert_eq!(utf16["range"]["start"]["character"], 16);
+        assert_eq!(utf16["range"]["end"]["character"], 20);
+        let units: Vec<u16> = code.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&units[16..20]).unwrap(), "eval");
+        assert_eq!(
+            utf16["relatedInformation"][0]["location"]["range"],
+            utf16["range"]
+        );
+        assert_eq!(utf16["message"], scalar["message"]);
+    }
+
+    #[test]
+    fn test_wide_emoji_inside_span_ends_after_its_characters() {
+        let code = "const s = '😀'; eval(s);";
+        // The underline is four display columns: two quotes and a wide emoji
+        let diagnostic = Diagnostic::builder()
+            .source(code)
+            .line(1)
+            .col(11)
+            .underline(4)
+            .build();
+
+        let scalar: Value = serde_json::from_str(&to_lsp_json(&diagnostic)).unwrap();
+        let utf16: Value = serde_json::from_str(&to_lsp_utf16(&diagnostic, code)).unwrap();
+
+        assert_eq!(scalar["range"]["start"]["character"], 10);
+        assert_eq!(scalar["range"]["end"]["character"], 13);
+        assert_eq!(utf16["range"]["start"]["character"], 10);
+        assert_eq!(utf16["range"]["end"]["character"], 14);
+        let units: Vec<u16> = code.encode_utf16().collect();
+        assert_eq!(String::from_utf16(&units[10..14]).unwrap(), "'😀'");
+    }
+
+    #[test]
+    fn test_lsp_severity_and_missing_help() {
+        let diagnostic = Diagnostic::builder()
+            .file("contract.ts")
+            .message("say \"hi\"")
+            .severity(Severity::Warning)
+            .build();
+
+        let value: Value = serde_json::from_str(&to_lsp_json(&diagnostic)).unwrap();
+
+        assert_eq!(value["severity"], 2);
+        assert_eq!(value["message"], "say \"hi\"");
+        assert!(value.get("relatedInformation").is_none());
+    }
+}
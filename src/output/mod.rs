@@ -0,0 +1,44 @@
+//! Encodings of diagnostics other than the text box, for editors, tooling, and logs
+
+mod diff;
+mod github;
+mod html;
+mod json;
+mod lsp;
+mod monaco;
+mod oneline;
+mod sarif;
+mod tap;
+
+pub use diff::format_fix_diff;
+pub use github::to_github_annotation;
+pub(crate) use html::to_html;
+pub use lsp::{to_lsp_json, to_lsp_utf16};
+pub use monaco::to_monaco_marker;
+pub use oneline::{format_inline, format_oneline};
+pub use sarif::to_sarif;
+pub use tap::to_tap;
+
+/// Turn a file path into a URI, leaving existing URIs and relative paths alone
+pub(crate) fn file_uri(path: &str) -> String {
+    if path.contains("://") || !path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("file://{}", encode_path(path))
+    }
+}
+
+/// Percent-encode the characters of `path` that would end or break a URI path
+pub(crate) fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for ch in path.chars() {
+        match ch {
+            '%' => encoded.push_str("%25"),
+            ' ' => encoded.push_str("%20"),
+            '#' => encoded.push_str("%23"),
+            '?' => encoded.push_str("%3F"),
+            ch => encoded.push(ch),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,97 @@
+//! Markers for the Monaco editor used by tana-playground
+
+use super::json;
+use crate::{Diagnostic, Severity};
+
+/// Encode a diagnostic as a Monaco `IMarkerData` JSON object
+///
+/// The object has exactly the fields the playground reads:
+/// `startLineNumber`, `startColumn`, `endLineNumber`, `endColumn`, `message`
+/// and `severity`. Positions stay 1-indexed, as Monaco expects, and the end
+/// column is just past the characters the underline covers on the start
+/// line, or the end of the diagnostic's [`Span::range`](crate::Span::range)
+/// when it has one.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{to_monaco_marker, Diagnostic};
+///
+/// let diagnostic = Diagnostic::builder().line(2).col(5).underline(3).message("bad").build();
+///
+/// assert_eq!(
+///     to_monaco_marker(&diagnostic),
+///     r#"{"startLineNumber":2,"startColumn":5,"endLineNumber":2,"endColumn":8,"message":"bad","severity":8}"#
+/// );
+/// ```
+pub fn to_monaco_marker(diagnostic: &Diagnostic) -> String {
+    let (end_line, end_col) = diagnostic.end_position();
+    format!(
+        r#"{{"startLineNumber":{},"startColumn":{},"endLineNumber":{},"endColumn":{},"message":{},"severity":{}}}"#,
+        diagnostic.span.line,
+        diagnostic.span.col,
+        end_line,
+        end_col,
+        json::string(&diagnostic.message),
+        monaco_severity(diagnostic.severity)
+    )
+}
+
+/// Monaco `MarkerSeverity` number for a severity
+fn monaco_severity(severity: Severity) -> u8 {
+    match severity {
+        Severity::Error => 8,
+        Severity::Warning => 4,
+        Severity::Info => 2,
+        Severity::Hint => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_monaco_marker_fields() {
+        let code = "import { console } from 'tana/invalid';";
+        let diagnostic = Diagnostic::builder()
+            .source(code)
+            .line(1)
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .build();
+
+        let value: Value = serde_json::from_str(&to_monaco_marker(&diagnostic)).unwrap();
+        let start = value["startColumn"].as_u64().unwrap() as usize;
+        let end = value["endColumn"].as_u64().unwrap() as usize;
+
+        assert_eq!(value.as_object().unwrap().len(), 6);
+        assert_eq!(
+            (
+                value["startLineNumber"].as_u64(),
+                value["endLineNumber"].as_u64()
+            ),
+            (Some(1), Some(1))
+        );
+        assert_eq!(end, 38);
+        assert_eq!(&code[start - 1..end - 1], "tana/invalid");
+    }
+
+    #[test]
+    fn test_monaco_severity_numbers() {
+        let severities = [
+            (Severity::Error, 8),
+            (Severity::Warning, 4),
+            (Severity::Info, 2),
+            (Severity::Hint, 1),
+        ];
+
+        for (severity, expected) in severities {
+            let diagnostic = Diagnostic::builder().severity(severity).build();
+            let value: Value = serde_json::from_str(&to_monaco_marker(&diagnostic)).unwrap();
+            assert_eq!(value["severity"], expected);
+        }
+    }
+}
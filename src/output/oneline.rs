@@ -0,0 +1,144 @@
+//! Compact single-line text for structured logs and tooltips
+
+use crate::{render, Diagnostic};
+
+/// Format a diagnostic as one gcc-style line
+///
+/// Produces `{file}:{line}:{col}: {kind}: {message}`, followed by
+/// ` (help: {help})` when there is help. Line breaks inside the message or
+/// help are replaced with spaces, so the output never contains a newline.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_oneline, Diagnostic};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .file("contract.ts")
+///     .kind("Invalid Import")
+///     .line(1)
+///     .col(26)
+///     .message("Module 'tana/invalid' not found")
+///     .help("Available modules: tana/core, tana/kv")
+///     .build();
+///
+/// assert_eq!(
+///     format_oneline(&diagnostic),
+///     "contract.ts:1:26: Invalid Import: Module 'tana/invalid' not found (help: Available modules: tana/core, tana/kv)"
+/// );
+/// ```
+pub fn format_oneline(diagnostic: &Diagnostic) -> String {
+    let mut out = format!(
+        "{}:{}:{}: {}: {}",
+        diagnostic.file_path,
+        diagnostic.span.line,
+        diagnostic.span.col,
+        diagnostic.kind,
+        diagnostic.message
+    );
+    if let Some(help) = diagnostic.help.as_deref().filter(|help| !help.is_empty()) {
+        out.push_str(&format!(" (help: {})", help));
+    }
+
+    join_lines(&out)
+}
+
+/// Format a diagnostic as one styled line for compact display, such as a
+/// tooltip
+///
+/// Produces `{icon} {kind}: {message} — help: {help}`, leaving out the
+/// location box and source snippet. The icon and labels come from the
+/// diagnostic's box style, and when its color choice allows, the icon and
+/// kind are colored by severity. The help part is left out when there is
+/// no help, and line breaks become spaces.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_inline, Diagnostic};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .kind("Invalid Import")
+///     .message("Module 'tana/invalid' not found")
+///     .help("did you mean 'tana/kv'?")
+///     .build();
+///
+/// assert_eq!(
+///     format_inline(&diagnostic),
+///     "❌ Invalid Import: Module 'tana/invalid' not found — help: did you mean 'tana/kv'?"
+/// );
+/// ```
+pub fn format_inline(diagnostic: &Diagnostic) -> String {
+    render::inline(diagnostic, &diagnostic.options.box_style.theme())
+}
+
+/// Replace each line break, including `\r\n`, with a single space
+fn join_lines(text: &str) -> String {
+    text.replace("\r\n", " ").replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorChoice, Severity};
+
+    #[test]
+    fn test_oneline_has_no_newlines() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;\nlet y = 2;")
+            .file("contract.ts")
+            .kind("Type Error")
+            .line(2)
+            .col(5)
+            .message("first line\nsecond line")
+            .help("fix it\r\nsoon")
+            .build();
+        let line = format_oneline(&diagnostic);
+
+        assert_eq!(line.matches('\n').count(), 0);
+        assert_eq!(
+            line,
+            "contract.ts:2:5: Type Error: first line second line (help: fix it soon)"
+        );
+    }
+
+    #[test]
+    fn test_inline_has_no_box() {
+        let diagnostic = Diagnostic::builder()
+            .source("import { console } from 'tana/invalid';")
+            .file("contract.ts")
+            .kind("Invalid Import")
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules:\ntana/core, tana/kv");
+        let line = format_inline(&diagnostic.clone().build());
+
+        assert!(!line.contains(['┌', '─', '│', '└', '^', '\n']));
+        assert!(!line.contains("contract.ts"));
+        assert_eq!(
+            line,
+            "❌ Invalid Import: Module 'tana/invalid' not found — help: Available modules: tana/core, tana/kv"
+        );
+
+        let warning = diagnostic
+            .severity(Severity::Warning)
+            .color(ColorChoice::Always)
+            .build();
+        assert!(format_inline(&warning).starts_with("\x1b[33m⚠️ Invalid Import\x1b[0m: "));
+    }
+
+    #[test]
+    fn test_oneline_without_help() {
+        let diagnostic = Diagnostic::builder()
+            .file("contract.ts")
+            .kind("Type Error")
+            .message("msg")
+            .build();
+
+        assert_eq!(
+            format_oneline(&diagnostic),
+            "contract.ts:1:1: Type Error: msg"
+        );
+    }
+}
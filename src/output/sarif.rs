@@ -0,0 +1,150 @@
+//! SARIF 2.1.0 logs for security tooling and code scanning dashboards
+
+use super::{file_uri, json};
+use crate::{Diagnostic, Severity};
+
+/// Encode diagnostics as a SARIF 2.1.0 log with a single run
+///
+/// Each diagnostic becomes one `result` whose `ruleId` is the error code, or
+/// the kind when there is none, and whose `level` follows the severity.
+/// Regions use SARIF's 1-indexed lines and columns, counted in characters,
+/// which the run declares with `"columnKind":"unicodeCodePoints"`.
+/// `endColumn` is the column just past the underlined characters, and a
+/// span covering several lines adds its `endLine`.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{to_sarif, Diagnostic};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .file("contract.ts")
+///     .kind("Invalid Import")
+///     .line(1)
+///     .col(26)
+///     .underline(12)
+///     .message("Module 'tana/invalid' not found")
+///     .build();
+///
+/// let sarif = to_sarif(&[diagnostic]);
+/// assert!(sarif.contains(r#""region":{"startLine":1,"startColumn":26,"endColumn":38}"#));
+/// ```
+pub fn to_sarif(diagnostics: &[Diagnostic]) -> String {
+    let results: Vec<String> = diagnostics.iter().map(sarif_result).collect();
+
+    format!(
+        concat!(
+            r#"{{"version":"2.1.0","#,
+            r#""$schema":"https://json.schemastore.org/sarif-2.1.0.json","#,
+            r#""runs":[{{"tool":{{"driver":{{"name":"tana-validation","version":{},"#,
+            r#""informationUri":"https://github.com/tananetwork/tana-validation"}}}},"#,
+            r#""columnKind":"unicodeCodePoints","results":[{}]}}]}}"#
+        ),
+        json::string(env!("CARGO_PKG_VERSION")),
+        results.join(",")
+    )
+}
+
+/// One SARIF `result` object
+fn sarif_result(diagnostic: &Diagnostic) -> String {
+    let end_column = match diagnostic.span.end {
+        Some(_) => diagnostic.end_position().1,
+        // An insertion point still covers one character
+        None => diagnostic.end_position().1.max(diagnostic.span.col + 1),
+    };
+    let end_line = match diagnostic.last_line() {
+        line if line > diagnostic.span.line => format!(r#","endLine":{}"#, line),
+        _ => String::new(),
+    };
+
+    format!(
+        concat!(
+            r#"{{"ruleId":{},"level":"{}","message":{{"text":{}}},"#,
+            r#""locations":[{{"physicalLocation":{{"artifactLocation":{{"uri":{}}},"#,
+            r#""region":{{"startLine":{},"startColumn":{}{},"endColumn":{}}}}}}}]}}"#
+        ),
+        json::string(diagnostic.code.as_deref().unwrap_or(&diagnostic.kind)),
+        sarif_level(diagnostic.severity),
+        json::string(&diagnostic.message),
+        json::string(&file_uri(&diagnostic.file_path)),
+        diagnostic.span.line,
+        diagnostic.span.col,
+        end_line,
+        end_column
+    )
+}
+
+/// SARIF `level` for a severity
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Hint => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_sarif_region_for_one_diagnostic() {
+        let diagnostic = Diagnostic::builder()
+            .source("import { console } from 'tana/invalid';")
+            .file("/work/contract.ts")
+            .kind("Invalid Import")
+            .line(1)
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .severity(Severity::Warning)
+            .build();
+
+        let log: Value = serde_json::from_str(&to_sarif(&[diagnostic])).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "tana-validation");
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "Invalid Import");
+        assert_eq!(result["level"], "warning");
+        assert_eq!(result["message"]["text"], "Module 'tana/invalid' not found");
+
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(
+            location["artifactLocation"]["uri"],
+            "file:///work/contract.ts"
+        );
+        assert_eq!(location["region"]["startLine"], 1);
+        assert_eq!(location["region"]["startColumn"], 26);
+        assert_eq!(location["region"]["endColumn"], 38);
+    }
+
+    #[test]
+    fn test_sarif_columns_count_code_points() {
+        let diagnostic = Diagnostic::builder()
+            .source("const s = '😀';")
+            .line(1)
+            .col(11)
+            .underline(4)
+            .build();
+
+        let log: Value = serde_json::from_str(&to_sarif(&[diagnostic])).unwrap();
+        let run = &log["runs"][0];
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+
+        // The emoji is two display columns wide but one code point
+        let region = &run["results"][0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startColumn"], 11);
+        assert_eq!(region["endColumn"], 14);
+    }
+
+    #[test]
+    fn test_sarif_with_no_diagnostics() {
+        let log: Value = serde_json::from_str(&to_sarif(&[])).unwrap();
+
+        assert_eq!(log["runs"][0]["results"], Value::Array(Vec::new()));
+    }
+}
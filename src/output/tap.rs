@@ -0,0 +1,123 @@
+//! Test Anything Protocol streams for contract test harnesses
+
+use super::json;
+use crate::{Diagnostic, Severity};
+
+/// Encode diagnostics as a TAP version 13 stream
+///
+/// The stream opens with `TAP version 13` and the plan `1..N`, then each
+/// diagnostic becomes one `not ok N - {kind}: {message}` test point followed
+/// by a YAML block with its file, line, column, severity and help. Errors
+/// fail the run; other severities are marked `# TODO`, so a harness reports
+/// them without failing. Line breaks in the description become spaces, and
+/// `#` and `\` are escaped so they can't start a directive.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{to_tap, Diagnostic};
+///
+/// let diagnostic = Diagnostic::builder()
+///     .file("contract.ts")
+///     .kind("Invalid Import")
+///     .line(1)
+///     .col(26)
+///     .message("Module 'tana/invalid' not found")
+///     .build();
+///
+/// assert_eq!(
+///     to_tap(&[diagnostic]),
+///     "TAP version 13\n\
+///      1..1\n\
+///      not ok 1 - Invalid Import: Module 'tana/invalid' not found\n  \
+///        ---\n  \
+///        file: \"contract.ts\"\n  \
+///        line: 1\n  \
+///        col: 26\n  \
+///        severity: \"error\"\n  \
+///        ...\n"
+/// );
+/// ```
+pub fn to_tap(diagnostics: &[Diagnostic]) -> String {
+    let mut out = format!("TAP version 13\n1..{}\n", diagnostics.len());
+
+    for (index, diagnostic) in diagnostics.iter().enumerate() {
+        let description = escape(&format!("{}: {}", diagnostic.kind, diagnostic.message));
+        let severity = diagnostic.severity.name();
+        let directive = match diagnostic.severity {
+            Severity::Error => String::new(),
+            _ => format!(" # TODO {}", severity),
+        };
+        out.push_str(&format!(
+            "not ok {} - {}{}\n  ---\n  file: {}\n  line: {}\n  col: {}\n  severity: {}\n",
+            index + 1,
+            description,
+            directive,
+            json::string(&diagnostic.file_path),
+            diagnostic.span.line,
+            diagnostic.span.col,
+            json::string(severity),
+        ));
+        if let Some(help) = diagnostic.help.as_deref().filter(|help| !help.is_empty()) {
+            out.push_str(&format!("  help: {}\n", json::string(help)));
+        }
+        out.push_str("  ...\n");
+    }
+
+    out
+}
+
+/// Keep a test point's description on one line and free of directives
+fn escape(description: &str) -> String {
+    description
+        .replace('\\', "\\\\")
+        .replace('#', "\\#")
+        .replace("\r\n", " ")
+        .replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_counts_every_diagnostic() {
+        let diagnostics: Vec<Diagnostic> = (1..=3)
+            .map(|line| {
+                Diagnostic::builder()
+                    .file("contract.ts")
+                    .kind("Forbidden Global")
+                    .line(line)
+                    .message("'eval' is not available in contracts")
+                    .help("remove it")
+                    .build()
+            })
+            .collect();
+        let tap = to_tap(&diagnostics);
+
+        assert!(tap.starts_with("TAP version 13\n1..3\n"));
+        assert_eq!(tap.matches("\nnot ok ").count(), 3);
+        for n in 1..=3 {
+            assert!(tap.contains(&format!(
+                "not ok {} - Forbidden Global: 'eval' is not available in contracts\n",
+                n
+            )));
+            assert!(tap.contains(&format!("  line: {}\n", n)));
+        }
+        assert!(tap.contains("  help: \"remove it\"\n"));
+        assert_eq!(to_tap(&[]), "TAP version 13\n1..0\n");
+    }
+
+    #[test]
+    fn test_non_errors_are_todo_and_descriptions_escaped() {
+        let diagnostic = Diagnostic::builder()
+            .kind("Unused Binding")
+            .message("'#x' is never read\nremove it")
+            .severity(Severity::Warning)
+            .build();
+
+        assert!(to_tap(&[diagnostic]).contains(
+            "not ok 1 - Unused Binding: '\\#x' is never read remove it # TODO warning\n"
+        ));
+    }
+}
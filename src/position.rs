@@ -0,0 +1,247 @@
+//! Conversion between byte offsets and line/column positions
+//!
+//! Lines and columns are 1-indexed, and columns count Unicode scalar values
+//! (Rust `char`s), matching the positions accepted by the formatter.
+
+/// Where a caller's line and column numbers start counting
+///
+/// Parsers commonly report zero-indexed positions; choosing
+/// [`PositionBase::Zero`] lets them be passed as-is and converted to the
+/// 1-indexed positions shown in output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum PositionBase {
+    /// The first line and column are `0`
+    Zero,
+    /// The first line and column are `1`
+    #[default]
+    One,
+}
+
+impl PositionBase {
+    /// The 1-indexed equivalent of `n`, counted from this base
+    pub(crate) fn to_one_indexed(self, n: usize) -> usize {
+        match self {
+            PositionBase::Zero => n + 1,
+            PositionBase::One => n,
+        }
+    }
+}
+
+/// 1-indexed line and character column of the byte `offset` into `code`
+///
+/// Offsets past the end of `code` are clamped to its end, and offsets inside
+/// a multibyte character resolve to the start of that character. An offset
+/// pointing at a newline belongs to the line that newline ends, and a `\r\n`
+/// terminator is treated as one, so no column ever counts the `\r`. A byte
+/// order mark opening `code` is not counted as a column.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::offset_to_position;
+///
+/// let code = "let a = 1;\nlet 名前 = a;";
+/// assert_eq!(offset_to_position(code, 11), (2, 1));
+/// assert_eq!(offset_to_position(code, 21), (2, 7));
+/// ```
+pub fn offset_to_position(code: &str, offset: usize) -> (usize, usize) {
+    let mut offset = floor_char_boundary(code, offset);
+    // `\r\n` ends a line as one unit; its `\n` sits where the `\r` does
+    if code[..offset].ends_with('\r') && code[offset..].starts_with('\n') {
+        offset -= 1;
+    }
+    let before = &code[..offset];
+    let line_start = before
+        .rfind('\n')
+        .map_or(bom_len(code).min(offset), |newline| newline + 1);
+
+    let line = before.matches('\n').count() + 1;
+    let col = before[line_start..].chars().count() + 1;
+    (line, col)
+}
+
+/// Byte offset into `code` of the 1-indexed `line` and character column `col`
+///
+/// The inverse of [`offset_to_position`]. A column one past the last
+/// character of a line maps to the end of that line. Returns `None` when the
+/// line or column is zero or lies beyond the source.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::position_to_offset;
+///
+/// let code = "let a = 1;\nlet 名前 = a;";
+/// assert_eq!(position_to_offset(code, 2, 7), Some(21));
+/// assert_eq!(position_to_offset(code, 3, 1), None);
+/// ```
+pub fn position_to_offset(code: &str, line: usize, col: usize) -> Option<usize> {
+    if line == 0 || col == 0 {
+        return None;
+    }
+
+    let mut line_start = bom_len(code);
+    for _ in 1..line {
+        line_start += code[line_start..].find('\n')? + 1;
+    }
+    let line_end = line_end(code, line_start);
+
+    let text = &code[line_start..line_end];
+    match text.char_indices().nth(col - 1) {
+        Some((index, _)) => Some(line_start + index),
+        None if col - 1 == text.chars().count() => Some(line_end),
+        None => None,
+    }
+}
+
+/// 1-indexed column of `line:col` counted in UTF-16 code units
+///
+/// Characters before `col` on the line are measured in `code`, so one
+/// outside the Basic Multilingual Plane, such as most emoji, counts as two.
+/// Columns past the end of the line, or on a line past the end of `code`,
+/// count one unit per missing character.
+pub(crate) fn utf16_col(code: &str, line: usize, col: usize) -> usize {
+    let text = code.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+    let text = if line == 1 {
+        &text[bom_len(text)..]
+    } else {
+        text
+    };
+
+    let before = col.saturating_sub(1);
+    let measured: usize = text.chars().take(before).map(char::len_utf16).sum();
+    measured + before.saturating_sub(text.chars().count()) + 1
+}
+
+/// Byte offset where the line containing `offset` ends
+///
+/// That is the start of its `\n` or `\r\n` terminator, or the end of
+/// `code` on the last line.
+pub(crate) fn line_end(code: &str, offset: usize) -> usize {
+    match code[offset..].find('\n') {
+        Some(newline) => {
+            let end = offset + newline;
+            if code[..end].ends_with('\r') && end > offset {
+                end - 1
+            } else {
+                end
+            }
+        }
+        None => code.len(),
+    }
+}
+
+/// Byte length of the byte order mark opening `code`, or 0 without one
+///
+/// Some editors prepend a BOM. It is not part of the first line's text, so
+/// columns and rendered lines start after it.
+pub(crate) fn bom_len(code: &str) -> usize {
+    if code.starts_with(BOM) {
+        BOM.len_utf8()
+    } else {
+        0
+    }
+}
+
+const BOM: char = '\u{FEFF}';
+
+/// Largest char boundary in `code` at or before `offset`
+pub(crate) fn floor_char_boundary(code: &str, offset: usize) -> usize {
+    let mut offset = offset.min(code.len());
+    while !code.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_to_position() {
+        let code = "let a = 1;\nlet 名前 = a;\n";
+
+        assert_eq!(offset_to_position(code, 0), (1, 1));
+        assert_eq!(offset_to_position(code, 4), (1, 5));
+        // The newline itself sits at the end of its line
+        assert_eq!(offset_to_position(code, 10), (1, 11));
+        assert_eq!(offset_to_position(code, 11), (2, 1));
+        // 名 and 前 are three bytes each but one column each
+        assert_eq!(offset_to_position(code, 21), (2, 7));
+        // Inside 前 resolves to its start
+        assert_eq!(offset_to_position(code, 19), (2, 6));
+        assert_eq!(offset_to_position(code, 1_000), (3, 1));
+    }
+
+    #[test]
+    fn test_position_to_offset_out_of_range() {
+        let code = "ab\ncd";
+
+        assert_eq!(position_to_offset(code, 0, 1), None);
+        assert_eq!(position_to_offset(code, 1, 0), None);
+        assert_eq!(position_to_offset(code, 1, 3), Some(2));
+        assert_eq!(position_to_offset(code, 1, 4), None);
+        assert_eq!(position_to_offset(code, 2, 3), Some(5));
+        assert_eq!(position_to_offset(code, 3, 1), None);
+        assert_eq!(position_to_offset("", 1, 1), Some(0));
+    }
+
+    #[test]
+    fn test_offset_position_round_trip() {
+        let code = "const 名 = \"é\";\n\n  🎉 done\nlast";
+
+        for (offset, _) in code.char_indices().chain([(code.len(), ' ')]) {
+            let (line, col) = offset_to_position(code, offset);
+            assert_eq!(position_to_offset(code, line, col), Some(offset));
+        }
+
+        // Line boundaries: the end of one line and the start of the next
+        assert_eq!(offset_to_position(code, 17), (1, 15));
+        assert_eq!(offset_to_position(code, 18), (2, 1));
+        assert_eq!(offset_to_position(code, 19), (3, 1));
+    }
+
+    #[test]
+    fn test_utf16_col_counts_astral_characters_twice() {
+        let code = "let a = 1;\nlet s = '😀'; eval(s);\r\nend";
+
+        assert_eq!(utf16_col(code, 1, 5), 5);
+        assert_eq!(utf16_col(code, 2, 15), 16);
+        assert_eq!(utf16_col(code, 2, 30), 31);
+        assert_eq!(utf16_col(code, 9, 3), 3);
+        assert_eq!(utf16_col("\u{FEFF}a😀b", 1, 4), 5);
+    }
+
+    #[test]
+    fn test_leading_bom_is_not_a_column() {
+        let code = "\u{FEFF}let x;\nlet y;";
+
+        assert_eq!(offset_to_position(code, 0), (1, 1));
+        assert_eq!(offset_to_position(code, 3), (1, 1));
+        assert_eq!(offset_to_position(code, 7), (1, 5));
+        assert_eq!(offset_to_position(code, 13), (2, 4));
+        assert_eq!(position_to_offset(code, 1, 1), Some(3));
+        assert_eq!(position_to_offset(code, 2, 4), Some(13));
+    }
+
+    #[test]
+    fn test_crlf_is_one_terminator() {
+        let code = "ab\r\ncd";
+
+        assert_eq!(offset_to_position(code, 4), (2, 1));
+        assert_eq!(offset_to_position(code, 5), (2, 2));
+        // Both bytes of the terminator sit at the end of line 1
+        assert_eq!(offset_to_position(code, 2), (1, 3));
+        assert_eq!(offset_to_position(code, 3), (1, 3));
+
+        assert_eq!(position_to_offset(code, 1, 3), Some(2));
+        assert_eq!(position_to_offset(code, 1, 4), None);
+        assert_eq!(position_to_offset(code, 2, 1), Some(4));
+    }
+}
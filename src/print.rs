@@ -0,0 +1,57 @@
+//! Printing diagnostics straight to the terminal
+
+use terminal_size::{terminal_size, Width};
+
+use crate::{ColorChoice, Diagnostic};
+
+/// Width printed to when stdout isn't a terminal or its size is unknown
+const FALLBACK_WIDTH: usize = 80;
+
+/// Print a diagnostic to stdout, fitted to the terminal
+///
+/// Color follows [`ColorChoice::Auto`], so it is emitted only when stdout is
+/// a terminal and `NO_COLOR` is unset, and the box is fitted to the
+/// terminal's width. When stdout is piped, or its size can't be read, the
+/// output is plain and fitted to 80 columns. Any narrower `render_width`
+/// set on the diagnostic is kept.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{print_diagnostic, Diagnostic};
+///
+/// print_diagnostic(&Diagnostic::builder().message("oops").build());
+/// ```
+pub fn print_diagnostic(diagnostic: &Diagnostic) {
+    let detected = terminal_size().map(|(Width(width), _)| usize::from(width));
+    let width = resolve_width(detected);
+
+    let mut diagnostic = diagnostic.clone();
+    diagnostic.color = ColorChoice::Auto;
+    diagnostic.options.render_width = Some(
+        diagnostic
+            .options
+            .render_width
+            .map_or(width, |render_width| render_width.min(width)),
+    );
+    print!("{}", diagnostic);
+}
+
+/// Width to fit output to, given the terminal's width if known
+fn resolve_width(detected: Option<usize>) -> usize {
+    detected
+        .filter(|&width| width > 0)
+        .unwrap_or(FALLBACK_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width_falls_back_to_80_without_a_terminal() {
+        assert_eq!(resolve_width(None), 80);
+        assert_eq!(resolve_width(Some(0)), 80);
+        assert_eq!(resolve_width(Some(132)), 132);
+    }
+}
@@ -0,0 +1,2208 @@
+//! Text rendering of diagnostics into the boxed Rust/Gleam-style layout
+
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+use crate::color::{Painter, Style};
+use crate::{position, text};
+use crate::{
+    Diagnostic, DiagnosticParts, FormatOptions, Hyperlinks, LabelPlacement, Severity, Theme,
+    TypeMismatch,
+};
+
+/// Everything printed around the snippet rows
+pub(crate) struct Frame<'a> {
+    pub severity: Severity,
+    pub kind: &'a str,
+    /// Failure-class code printed in brackets after the kind
+    pub code: Option<&'a str>,
+    /// Path in the location line; `None` prints just the line and column,
+    /// as under a report's per-file header
+    pub file_path: Option<&'a str>,
+    pub line_num: usize,
+    pub col_num: usize,
+    pub help: Option<Cow<'a, str>>,
+    pub note: Option<Cow<'a, str>>,
+    /// Cited locations, each as `path:line:col: note`, printed as
+    /// `= related:` lines after the help and note
+    pub related: Vec<String>,
+    /// Docs page printed as `= see {url}` after the help and note
+    pub docs_url: Option<&'a str>,
+    pub fix: Option<Fix>,
+    /// Expected and found types printed as two aligned lines beneath the
+    /// snippet
+    pub mismatch: Option<&'a TypeMismatch>,
+    pub color: bool,
+    pub theme: &'a Theme,
+    /// Word-wrap help rows wider than this many display columns
+    pub wrap_width: Option<usize>,
+    /// URI the location line links to with an OSC 8 escape
+    pub link: Option<String>,
+    /// Whether links are enabled, which makes the docs URL a link too
+    pub hyperlinks: bool,
+    /// Wrap text containing right-to-left scripts in directional isolates
+    pub isolate_bidi: bool,
+    /// Leave out the separator row above the bottom corner
+    pub trim_surrounding: bool,
+}
+
+impl Frame<'_> {
+    /// Whether any help, note or docs block follows the snippet
+    fn has_footnotes(&self) -> bool {
+        !self.related.is_empty()
+            || [self.help.as_deref(), self.note.as_deref(), self.docs_url]
+                .into_iter()
+                .any(|body| body.is_some_and(|body| !body.is_empty()))
+    }
+}
+
+/// A source line as it reads before and after a suggested fix
+pub(crate) struct Fix {
+    pub line_num: usize,
+    /// Original text with tabs already expanded
+    pub before: String,
+    /// Fixed text with tabs already expanded
+    pub after: String,
+}
+
+/// A source line in the snippet together with the underlines drawn beneath it
+pub(crate) struct SnippetRow<'a> {
+    pub line_num: usize,
+    /// Source text with tabs already expanded
+    pub text: String,
+    /// Underlines printed on their own rows below the source, ordered by
+    /// start column; rows with none are printed without an underline
+    pub marks: Vec<Mark<'a>>,
+}
+
+/// One underlined run of a source row and the message to its right
+pub(crate) struct Mark<'a> {
+    /// Display columns before the first underline character
+    pub padding: usize,
+    /// Number of underline characters; 0 prints the label alone
+    pub underline: usize,
+    /// Message printed after the underline
+    pub label: Option<Cow<'a, str>>,
+    /// Primary marks use the theme's caret for their severity, secondary
+    /// ones its secondary caret
+    pub primary: bool,
+    /// Severity of the diagnostic the mark belongs to
+    pub severity: Severity,
+    /// Print the label on the rows below the underline, starting under its
+    /// first character, instead of after it
+    pub below: bool,
+}
+
+/// Write the title line that opens every rendered diagnostic or report
+pub(crate) fn write_title<W: Write>(w: &mut W, severity: Severity, theme: &Theme) -> fmt::Result {
+    write!(w, "\n{}\n", theme.title(severity))
+}
+
+/// Render a diagnostic into the full error box drawn with `theme`
+pub(crate) fn render(diagnostic: &Diagnostic, theme: &Theme) -> String {
+    let mut out = String::new();
+    write(&mut out, diagnostic, theme).expect("writing to a String cannot fail");
+    out
+}
+
+/// Render a diagnostic as one line of icon, kind, message and help, with no
+/// box or snippet
+pub(crate) fn inline(diagnostic: &Diagnostic, theme: &Theme) -> String {
+    let painter = Painter::new(diagnostic.color.should_color(), theme.color_depth);
+    let (kind_style, _) = severity_styles(diagnostic.severity);
+    let isolated = |text: &str| {
+        if diagnostic.options.isolate_bidi {
+            text::isolate_rtl(text).into_owned()
+        } else {
+            text.to_string()
+        }
+    };
+    let one_line = |text: &str| text.replace("\r\n", " ").replace(['\n', '\r'], " ");
+
+    // Themes without an icon print the kind alone
+    let mut kind = [theme.icon(diagnostic.severity), &diagnostic.kind]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if let Some(code) = &diagnostic.code {
+        kind = format!("{} [{}]", kind, code);
+    }
+
+    let mut out = painter.paint(kind_style, &isolated(&kind)).to_string();
+    if !diagnostic.message.is_empty() {
+        out.push_str(&format!(": {}", isolated(&one_line(&diagnostic.message))));
+    }
+    if let Some(help) = diagnostic.help.as_deref().filter(|help| !help.is_empty()) {
+        out.push_str(&format!(
+            " — {} {}",
+            painter.paint(Style::Cyan, &format!("{}:", theme.labels.help)),
+            isolated(&one_line(help))
+        ));
+    }
+    out
+}
+
+/// Render a diagnostic's suggested fix as a diff of the line it changes,
+/// under its location and one-line summary; without a fix, or when the fix
+/// names a line `source` doesn't have, the full error box
+pub(crate) fn fix_diff(diagnostic: &Diagnostic, theme: &Theme) -> String {
+    let (frame, _) = layout(diagnostic, theme);
+    let has_line = |line| {
+        source_window(&diagnostic.source, line, line)
+            .next()
+            .is_some()
+    };
+    let fix = frame.fix.as_ref().filter(|_| {
+        diagnostic
+            .suggestion
+            .as_ref()
+            .is_some_and(|suggestion| has_line(suggestion.line))
+    });
+    let Some(fix) = fix else {
+        return render(diagnostic, theme);
+    };
+    let painter = Painter::new(frame.color, theme.color_depth);
+
+    let location = match frame.file_path.filter(|path| !path.is_empty()) {
+        Some(path) => format!("{}:{}:{}", path, frame.line_num, frame.col_num),
+        None => format!("{}:{}", frame.line_num, frame.col_num),
+    };
+    let mut out = format!(
+        "{}: {}\n",
+        isolated(&frame, &location),
+        inline(diagnostic, theme)
+    );
+    for (sign, text, style) in [
+        ("-", &fix.before, Style::Red),
+        ("+", &fix.after, Style::Green),
+    ] {
+        let row = format!(
+            "{} {} {} {}",
+            sign,
+            fix.line_num,
+            theme.gutter_char,
+            isolated(&frame, text)
+        );
+        out.push_str(&format!("{}\n", painter.paint(style, row.trim_end())));
+    }
+    out
+}
+
+/// Render each segment of a diagnostic's error box on its own
+pub(crate) fn parts(diagnostic: &Diagnostic, theme: &Theme) -> DiagnosticParts {
+    let (frame, rows) = layout(diagnostic, theme);
+    let segment = |segment| {
+        let mut out = String::new();
+        write_segment(&mut out, &frame, &rows, segment).expect("writing to a String cannot fail");
+        out
+    };
+
+    let mut title = String::new();
+    write_box_title(&mut title, diagnostic, theme).expect("writing to a String cannot fail");
+    DiagnosticParts {
+        title,
+        kind: segment(Segment::Kind),
+        location: segment(Segment::Location),
+        snippet: segment(Segment::Snippet),
+        help: segment(Segment::Help),
+        bottom: segment(Segment::Bottom),
+    }
+}
+
+/// Write a diagnostic's full error box into `w`
+pub(crate) fn write<W: Write>(w: &mut W, diagnostic: &Diagnostic, theme: &Theme) -> fmt::Result {
+    write_box_title(w, diagnostic, theme)?;
+    write_body(w, diagnostic, theme)
+}
+
+/// Write the title line of a diagnostic's own box, without the blank line
+/// before it when the box is trimmed
+fn write_box_title<W: Write>(w: &mut W, diagnostic: &Diagnostic, theme: &Theme) -> fmt::Result {
+    if diagnostic.options.trim_surrounding {
+        writeln!(w, "{}", theme.title(diagnostic.severity))
+    } else {
+        write_title(w, diagnostic.severity, theme)
+    }
+}
+
+/// Write a diagnostic's kind line and snippet box, without the title
+pub(crate) fn write_body<W: Write>(
+    w: &mut W,
+    diagnostic: &Diagnostic,
+    theme: &Theme,
+) -> fmt::Result {
+    write_group_body(w, &[diagnostic], theme)
+}
+
+/// Write the kind line and one snippet box shared by a group of diagnostics
+/// from the same file, without the title
+pub(crate) fn write_group_body<W: Write>(
+    w: &mut W,
+    group: &[&Diagnostic],
+    theme: &Theme,
+) -> fmt::Result {
+    let (frame, rows) = layout_group(group, theme);
+    write_box_body(w, &frame, &rows)
+}
+
+/// Write a diagnostic's kind line and snippet box, leaving the file path out
+/// of the location line, without the title
+pub(crate) fn write_body_without_path<W: Write>(
+    w: &mut W,
+    diagnostic: &Diagnostic,
+    theme: &Theme,
+) -> fmt::Result {
+    let (mut frame, rows) = layout(diagnostic, theme);
+    frame.file_path = None;
+    write_box_body(w, &frame, &rows)
+}
+
+/// Work out what a diagnostic's box shows: its frame and the snippet rows
+pub(crate) fn layout<'a>(
+    diagnostic: &'a Diagnostic,
+    theme: &'a Theme,
+) -> (Frame<'a>, Vec<SnippetRow<'a>>) {
+    layout_group(&[diagnostic], theme)
+}
+
+/// Work out one box showing every diagnostic in `group`, all from one file
+///
+/// The first diagnostic sets the kind, location, options and suggested fix;
+/// each diagnostic adds its own underlines, and their distinct helps and
+/// notes are listed together. The box takes the most severe diagnostic's
+/// severity. Rows whose gap is at most `2 * context_lines` are joined by
+/// the source lines between them; larger gaps are folded when written.
+pub(crate) fn layout_group<'a>(
+    group: &[&'a Diagnostic],
+    theme: &'a Theme,
+) -> (Frame<'a>, Vec<SnippetRow<'a>>) {
+    let first = group[0];
+    let options = &first.options;
+
+    // Only the lines around each error are read; context lines outside the
+    // file are skipped rather than shown empty
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    let mut missing = Vec::new();
+    for diagnostic in group {
+        let context = diagnostic.options.context_lines;
+        let start = diagnostic.span.line.saturating_sub(context).max(1);
+        let end = diagnostic.last_line().saturating_add(context);
+        lines.extend(source_window(&diagnostic.source, start, end));
+        if diagnostic.options.show_prev_line {
+            let above = source_window(
+                &diagnostic.source,
+                1,
+                diagnostic.span.line.saturating_sub(1),
+            )
+            .filter(|(_, line)| !line.trim().is_empty())
+            .last();
+            lines.extend(above);
+        }
+
+        // The error row is printed even past the end of the file, and
+        // secondary labels outside the context window bring their own row
+        if !lines
+            .iter()
+            .any(|(line_num, _)| *line_num == diagnostic.span.line)
+        {
+            lines.push((diagnostic.span.line, ""));
+            missing.push(diagnostic.span.line);
+        }
+        for label in &diagnostic.secondary_labels {
+            lines.extend(source_window(&diagnostic.source, label.line, label.line));
+        }
+    }
+    lines.sort_by_key(|(line_num, _)| *line_num);
+    lines.dedup_by_key(|(line_num, _)| *line_num);
+
+    // Nearby rows are joined by the lines between them
+    let max_gap = options.context_lines.saturating_mul(2);
+    let mut joined: Vec<(usize, &str)> = Vec::with_capacity(lines.len());
+    for (line_num, line) in lines {
+        if let Some(&(previous, _)) = joined.last() {
+            if line_num - previous - 1 <= max_gap {
+                joined.extend(source_window(&first.source, previous + 1, line_num - 1));
+            }
+        }
+        joined.push((line_num, line));
+    }
+
+    // A render width caps source text to what fits after the gutter
+    let fix_line = first
+        .suggestion
+        .as_ref()
+        .map_or(0, |suggestion| suggestion.line);
+    let last_line = joined.last().map_or(0, |&(line_num, _)| line_num);
+    let gutter = (last_line.max(fix_line) + options.line_offset)
+        .to_string()
+        .len()
+        .max(3)
+        + 3;
+    let text_width = options
+        .render_width
+        .map(|render_width| render_width.saturating_sub(gutter));
+    let max_line_width = match (options.max_line_width, text_width) {
+        (Some(max), Some(fit)) => Some(max.min(fit)),
+        (max, fit) => max.or(fit),
+    };
+
+    // Overly long lines are cut around each error so its carets stay visible
+    let clip_around = |line: &str, col: usize, length: usize| {
+        clip_window(line, col, length, options, max_line_width, theme)
+    };
+    let clips: Vec<Option<text::Window>> = group
+        .iter()
+        .map(|diagnostic| {
+            let error_line = source_window(
+                &diagnostic.source,
+                diagnostic.span.line,
+                diagnostic.span.line,
+            )
+            .next()
+            .map_or("", |(_, line)| line);
+            clip_around(error_line, diagnostic.span.col, diagnostic.span.length)
+        })
+        .collect();
+
+    // Column annotation following a message, when columns are shown
+    let columns = |col: usize, end_col: Option<usize>| {
+        options.show_columns.then(|| match end_col {
+            Some(end) => theme
+                .labels
+                .column_span
+                .replace("{col}", &col.to_string())
+                .replace("{end}", &end.to_string()),
+            None => theme.labels.column.replace("{col}", &col.to_string()),
+        })
+    };
+
+    // Underline `length` columns of `line` from character column `col`,
+    // shifted into the truncation window, with the label and any column
+    // annotation wrapped to the render width
+    let mark = |line: &str,
+                clip: Option<text::Window>,
+                col,
+                length,
+                label: Option<&'a str>,
+                annotation: Option<String>,
+                primary,
+                severity| {
+        let padding = text::display_offset(line, col, options.tab_width);
+        let line_width = text::width(&text::expand_tabs(line, options.tab_width));
+        let (padding, underline) = text::clamp_caret(padding, length, line_width);
+        let (padding, underline) = match clip {
+            Some(clip) => clip.caret(padding, underline, line_width),
+            None => (padding, underline),
+        };
+        let label = label.filter(|label| !label.is_empty()).map(text::sanitize);
+        let label = match (label, annotation) {
+            (Some(label), Some(annotation)) => {
+                Some(Cow::Owned(format!("{} {}", label, annotation)))
+            }
+            (label, None) => label,
+            (None, annotation) => annotation.map(Cow::Owned),
+        };
+        let below = match options.label_placement {
+            LabelPlacement::Inline => false,
+            LabelPlacement::Below => true,
+            // Below only when the message would run past the render width
+            LabelPlacement::Auto => match (&label, text_width) {
+                (Some(label), Some(text_width)) => {
+                    padding + underline + 1 + text::widest_line(label) > text_width
+                }
+                _ => false,
+            },
+        };
+        let label_start = if below {
+            padding
+        } else {
+            padding + underline + 1
+        };
+        let label = label.map(|label| match text_width {
+            Some(text_width) => {
+                let available = text_width.saturating_sub(label_start).max(1);
+                let wrapped: Vec<String> = label
+                    .lines()
+                    .flat_map(|line| text::wrap(line, available))
+                    .collect();
+                Cow::Owned(wrapped.join("\n"))
+            }
+            None => label,
+        });
+        Mark {
+            padding,
+            underline,
+            label,
+            primary,
+            severity,
+            below,
+        }
+    };
+
+    let rows = joined
+        .iter()
+        .map(|&(line_num, line)| {
+            // A row is cut around the error or label it holds; other rows
+            // share the first error's cut unless they end before it starts
+            let line_width = text::width(&text::expand_tabs(line, options.tab_width));
+            let label = group
+                .iter()
+                .flat_map(|diagnostic| &diagnostic.secondary_labels)
+                .find(|label| label.line == line_num);
+            let clip = match group
+                .iter()
+                .position(|diagnostic| diagnostic.span.line == line_num)
+            {
+                Some(index) => clips[index],
+                None => match label {
+                    Some(label) => clip_around(line, label.col, label.length),
+                    None => match clips[0] {
+                        Some(clip) if clip.reaches(line_width) => Some(clip),
+                        _ => clip_around(line, 1, 0),
+                    },
+                },
+            };
+
+            let mut marks = Vec::new();
+            for diagnostic in group {
+                match diagnostic.span.end {
+                    None if line_num == diagnostic.span.line => {
+                        let length = diagnostic.span.length;
+                        marks.push(mark(
+                            line,
+                            clip,
+                            diagnostic.span.col,
+                            length,
+                            Some(&diagnostic.message),
+                            columns(diagnostic.span.col, None),
+                            true,
+                            diagnostic.severity,
+                        ));
+                    }
+                    // A range underlines its first line from the start
+                    // column, its last up to the end column, and the lines
+                    // between in full, with the message after the last
+                    Some((_, end_col))
+                        if (diagnostic.span.line..=diagnostic.last_line()).contains(&line_num) =>
+                    {
+                        let is_first = line_num == diagnostic.span.line;
+                        let is_last = line_num == diagnostic.last_line();
+                        let start = if is_first { diagnostic.span.col } else { 1 };
+                        let end = if is_last {
+                            end_col
+                        } else {
+                            line.chars().count() + 1
+                        };
+                        let width = text::display_offset(line, end, options.tab_width)
+                            .saturating_sub(text::display_offset(line, start, options.tab_width));
+                        let width = if is_first || is_last {
+                            width.max(1)
+                        } else {
+                            width
+                        };
+                        if width > 0 {
+                            let label = is_last.then_some(&*diagnostic.message);
+                            let annotation =
+                                columns(diagnostic.span.col, Some(end_col)).filter(|_| is_last);
+                            marks.push(mark(
+                                line,
+                                clip,
+                                start,
+                                width,
+                                label,
+                                annotation,
+                                true,
+                                diagnostic.severity,
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+                for label in &diagnostic.secondary_labels {
+                    if label.line == line_num {
+                        marks.push(mark(
+                            line,
+                            clip,
+                            label.col,
+                            label.length,
+                            Some(&label.message),
+                            columns(label.col, None),
+                            false,
+                            diagnostic.severity,
+                        ));
+                    }
+                }
+            }
+
+            // A line past the end of the file has nothing to underline, so
+            // its messages stand alone under a placeholder
+            if missing.contains(&line_num) {
+                for mark in &mut marks {
+                    mark.padding = 0;
+                    mark.underline = 0;
+                }
+                return SnippetRow {
+                    line_num: line_num + options.line_offset,
+                    text: theme.labels.line_unavailable.to_string(),
+                    marks,
+                };
+            }
+
+            // Each underline gets its own row, so overlapping spans never
+            // share one; they are listed left to right
+            marks.sort_by_key(|mark| mark.padding);
+
+            let expanded = text::expand_tabs(line, options.tab_width);
+            // Rows are looked up by their line in `source` but numbered as
+            // in the enclosing document
+            SnippetRow {
+                line_num: line_num + options.line_offset,
+                text: match clip {
+                    Some(clip) => clip.clip(&expanded),
+                    None => expanded,
+                },
+                marks,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let frame = Frame {
+        severity: group
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .min()
+            .unwrap_or(first.severity),
+        kind: &first.kind,
+        code: first.code.as_deref(),
+        file_path: Some(options.display_path(&first.file_path)),
+        line_num: first.span.line + options.line_offset,
+        col_num: first.span.col,
+        help: footnote(group, |diagnostic| diagnostic.help.as_deref()),
+        note: footnote(group, |diagnostic| diagnostic.note.as_deref()),
+        related: related(group),
+        docs_url: first.docs_url.as_deref(),
+        fix: first.suggestion.as_ref().map(|suggestion| {
+            let line = source_window(&first.source, suggestion.line, suggestion.line)
+                .next()
+                .map_or("", |(_, line)| line);
+            Fix {
+                line_num: suggestion.line + options.line_offset,
+                before: text::expand_tabs(line, options.tab_width),
+                after: text::expand_tabs(&suggestion.apply_to_line(line), options.tab_width),
+            }
+        }),
+        mismatch: first.type_mismatch.as_ref(),
+        color: first.color.should_color(),
+        theme,
+        wrap_width: match (options.wrap_width, options.render_width) {
+            (Some(wrap), Some(render)) => Some(wrap.min(render)),
+            (wrap, render) => wrap.or(render),
+        },
+        link: options.hyperlinks.uri(
+            &first.file_path,
+            options.workspace_root.as_deref(),
+            first.span.line + options.line_offset,
+            first.span.col,
+        ),
+        hyperlinks: options.hyperlinks != Hyperlinks::Off,
+        isolate_bidi: options.isolate_bidi,
+        trim_surrounding: options.trim_surrounding,
+    };
+
+    (frame, rows)
+}
+
+/// Truncation window around `length` columns of `line` from character
+/// column `col`, if lines are capped at `max_line_width`
+fn clip_window(
+    line: &str,
+    col: usize,
+    length: usize,
+    options: &FormatOptions,
+    max_line_width: Option<usize>,
+    theme: &Theme,
+) -> Option<text::Window> {
+    let max = max_line_width?;
+    let line_width = text::width(&text::expand_tabs(line, options.tab_width));
+
+    // Pad by display width so wide characters and tabs don't shift the carets
+    let padding = text::display_offset(line, col, options.tab_width);
+    let (padding, underline) = text::clamp_caret(padding, length, line_width);
+    Some(text::Window::around(
+        line_width,
+        padding,
+        underline,
+        max,
+        theme.ellipsis,
+    ))
+}
+
+/// The distinct, non-empty help or note texts of a group, one per line,
+/// with control characters made visible
+fn footnote<'a>(
+    group: &[&'a Diagnostic],
+    field: impl Fn(&'a Diagnostic) -> Option<&'a str>,
+) -> Option<Cow<'a, str>> {
+    let mut bodies: Vec<&str> = Vec::new();
+    for body in group.iter().filter_map(|diagnostic| field(diagnostic)) {
+        if !body.is_empty() && !bodies.contains(&body) {
+            bodies.push(body);
+        }
+    }
+    match bodies.as_slice() {
+        [] => None,
+        [body] => Some(text::sanitize(body)),
+        _ => Some(Cow::Owned(text::sanitize(&bodies.join("\n")).into_owned())),
+    }
+}
+
+/// The distinct related locations of a group as `path:line:col: note`, in
+/// order, with control characters made visible
+fn related(group: &[&Diagnostic]) -> Vec<String> {
+    let mut cited: Vec<String> = Vec::new();
+    for diagnostic in group {
+        for location in &diagnostic.related {
+            let path = diagnostic.options.display_path(&location.file_path);
+            let mut line = format!("{}:{}:{}", path, location.line, location.col);
+            if !location.note.is_empty() {
+                line = format!("{}: {}", line, location.note);
+            }
+            let line = text::sanitize(&line).into_owned();
+            if !cited.contains(&line) {
+                cited.push(line);
+            }
+        }
+    }
+    cited
+}
+
+/// Iterate the existing lines numbered `first..=last` (1-indexed) with their numbers
+///
+/// Lines are read lazily and iteration stops at `last`, so no more of
+/// `code` is scanned than needed.
+pub(crate) fn source_window(
+    code: &str,
+    first: usize,
+    last: usize,
+) -> impl Iterator<Item = (usize, &str)> {
+    let first = first.max(1);
+    let text = &code[position::bom_len(code)..];
+
+    // An empty source still has one, empty, line
+    text.lines()
+        .chain(text.is_empty().then_some(""))
+        .enumerate()
+        .skip(first - 1)
+        .take((last + 1).saturating_sub(first))
+        .map(|(index, line)| {
+            // A stray carriage return would corrupt the box, so CRLF and LF
+            // sources must render identically
+            (index + 1, line.strip_suffix('\r').unwrap_or(line))
+        })
+}
+
+/// Kind and caret colors for each severity
+fn severity_styles(severity: Severity) -> (Style, Style) {
+    match severity {
+        Severity::Error => (Style::Red, Style::BrightRed),
+        Severity::Warning => (Style::Yellow, Style::BrightYellow),
+        Severity::Info => (Style::Blue, Style::BrightBlue),
+        Severity::Hint => (Style::Cyan, Style::BrightCyan),
+    }
+}
+
+/// Width of the line-number gutter: wide enough for every row, and at least 3
+pub(crate) fn gutter_width(rows: &[SnippetRow]) -> usize {
+    rows.iter()
+        .map(|row| row.line_num.to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max(3)
+}
+
+/// The pieces of a box, in the order they are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Segment {
+    /// The kind line and the blank line after it
+    Kind,
+    /// The location line and the separator beneath it
+    Location,
+    /// The source rows with their underlines, and any suggested fix
+    Snippet,
+    /// The help, note and docs blocks, each with its separator
+    Help,
+    /// The corner closing the box
+    Bottom,
+}
+
+impl Segment {
+    /// Every segment, in print order
+    pub(crate) const ALL: [Segment; 5] = [
+        Segment::Kind,
+        Segment::Location,
+        Segment::Snippet,
+        Segment::Help,
+        Segment::Bottom,
+    ];
+}
+
+/// Write the kind line and snippet box, without the title
+fn write_box_body<W: Write>(w: &mut W, frame: &Frame, rows: &[SnippetRow]) -> fmt::Result {
+    for segment in Segment::ALL {
+        write_segment(w, frame, rows, segment)?;
+    }
+    Ok(())
+}
+
+/// Write one segment of the box laid out as `frame` and `rows`
+pub(crate) fn write_segment<W: Write>(
+    w: &mut W,
+    frame: &Frame,
+    rows: &[SnippetRow],
+    segment: Segment,
+) -> fmt::Result {
+    let w = &mut TrimTrailing::new(w);
+    let painter = Painter::new(frame.color, frame.theme.color_depth);
+    let (kind_style, _) = severity_styles(frame.severity);
+    let fix_width = frame
+        .fix
+        .as_ref()
+        .map_or(0, |fix| fix.line_num.to_string().len());
+    let width = gutter_width(rows).max(fix_width);
+    let blank = "";
+
+    let theme = frame.theme;
+
+    // Every row starts with the same gutter, so `┌─`, `│`, `=` and `└─`
+    // form one straight column
+    let mut gutter = [0; 4];
+    let bar = painter.paint(Style::Dim, theme.gutter_char.encode_utf8(&mut gutter));
+    // Rows with nothing else on them may use their own separator, or none
+    let mut empty_gutter = [0; 4];
+    let empty = theme.empty_gutter();
+    let separator = if empty.is_whitespace() {
+        painter.paint(Style::Dim, "")
+    } else {
+        painter.paint(Style::Dim, empty.encode_utf8(&mut empty_gutter))
+    };
+
+    match segment {
+        Segment::Kind => {
+            // Themes without an icon print the kind alone
+            let mut kind = match theme.icon(frame.severity) {
+                "" => frame.kind.to_string(),
+                icon => format!("{} {}", icon, frame.kind),
+            };
+            if let Some(code) = frame.code {
+                kind = format!("{} [{}]", kind, code);
+            }
+
+            write!(
+                w,
+                "{}\n\n",
+                painter.paint(kind_style, &isolated(frame, &kind))
+            )
+        }
+        Segment::Location => {
+            let mut location = match frame.file_path {
+                Some(path) => format!("{}:{}:{}", path, frame.line_num, frame.col_num),
+                None => format!("{}:{}", frame.line_num, frame.col_num),
+            };
+            location = isolated(frame, &location).into_owned();
+            if let Some(link) = &frame.link {
+                location = format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", link, location);
+            }
+
+            write!(
+                w,
+                "{blank:width$} {} {}\n\
+                {blank:width$} {separator}\n",
+                painter.paint(Style::Dim, theme.top),
+                location,
+            )
+        }
+        Segment::Snippet => {
+            for (index, row) in rows.iter().enumerate() {
+                // Skipped lines fold into a single marker row
+                if index > 0 && row.line_num > rows[index - 1].line_num + 1 {
+                    writeln!(w, "{:>width$} {bar}", theme.fold)?;
+                }
+                writeln!(
+                    w,
+                    "{:>width$} {bar} {}",
+                    row.line_num,
+                    isolated(frame, &row.text)
+                )?;
+
+                for mark in &row.marks {
+                    let (glyph, style) = if mark.primary {
+                        (theme.caret(mark.severity), severity_styles(mark.severity).1)
+                    } else {
+                        (theme.secondary_caret_char, Style::Blue)
+                    };
+                    write!(
+                        w,
+                        "{blank:width$} {bar} {blank:padding$}{}",
+                        painter.paint(style, &glyph.to_string().repeat(mark.underline)),
+                        padding = mark.padding,
+                    )?;
+                    let mut label_lines = mark.label.as_deref().into_iter().flat_map(str::lines);
+                    let indent = if mark.below && mark.underline > 0 {
+                        // The message starts on the next row, under the first caret
+                        mark.padding
+                    } else {
+                        // A mark without underline characters is a bare message
+                        let gap = usize::from(mark.underline > 0);
+                        if let Some(first) = label_lines.next() {
+                            write!(w, "{blank:gap$}{}", isolated(frame, first))?;
+                        }
+                        mark.padding + mark.underline + gap
+                    };
+                    writeln!(w)?;
+
+                    // Further lines of the message line up under its first line
+                    for line in label_lines {
+                        writeln!(
+                            w,
+                            "{blank:width$} {bar} {blank:indent$}{}",
+                            isolated(frame, line)
+                        )?;
+                    }
+                }
+            }
+
+            // A trimmed box has no separator above its bottom corner
+            let closes_box = frame.trim_surrounding && !frame.has_footnotes();
+            if !closes_box || frame.mismatch.is_some() || frame.fix.is_some() {
+                writeln!(w, "{blank:width$} {separator}")?;
+            }
+
+            // A type mismatch lines up the expected and found types by their colons
+            if let Some(mismatch) = frame.mismatch {
+                let expected = format!("{}:", theme.labels.expected);
+                let found = format!("{}:", theme.labels.found_type);
+                let label_width = text::width(&expected).max(text::width(&found));
+                for (label, type_name, style) in [
+                    (expected, &mismatch.expected, Style::Green),
+                    (found, &mismatch.found, Style::Red),
+                ] {
+                    let pad = label_width - text::width(&label);
+                    writeln!(
+                        w,
+                        "{blank:width$} {bar} {blank:pad$}{} {}",
+                        label,
+                        painter.paint(style, &isolated(frame, type_name))
+                    )?;
+                }
+                if !closes_box || frame.fix.is_some() {
+                    writeln!(w, "{blank:width$} {separator}")?;
+                }
+            }
+
+            // A suggested fix shows the line before and after the change
+            if let Some(fix) = &frame.fix {
+                writeln!(
+                    w,
+                    "{blank:width$} {}",
+                    painter.paint(Style::Cyan, &theme.suggestion_label())
+                )?;
+                writeln!(
+                    w,
+                    "{:>width$} {} {}",
+                    fix.line_num,
+                    painter.paint(Style::Red, "-"),
+                    isolated(frame, &fix.before)
+                )?;
+                writeln!(
+                    w,
+                    "{:>width$} {} {}",
+                    fix.line_num,
+                    painter.paint(Style::Green, "+"),
+                    isolated(frame, &fix.after)
+                )?;
+                if !closes_box {
+                    writeln!(w, "{blank:width$} {separator}")?;
+                }
+            }
+            Ok(())
+        }
+        Segment::Help => {
+            // Help and note are optional; a missing one drops its block and separator
+            let footnotes: Vec<(String, &str)> = [
+                (theme.help_label(), frame.help.as_deref()),
+                (theme.note_label(), frame.note.as_deref()),
+            ]
+            .into_iter()
+            .filter_map(|(label, body)| Some((label, body.filter(|body| !body.is_empty())?)))
+            .collect();
+            let docs_url = frame.docs_url.filter(|url| !url.is_empty());
+            for (index, (label, body)) in footnotes.iter().enumerate() {
+                // Continuation lines of multi-line text line up after `= help: `
+                let indent = width + 1 + text::width(label) + 1;
+                let mut lines = body.lines().flat_map(|line| match frame.wrap_width {
+                    Some(wrap_width) => text::wrap(line, wrap_width.saturating_sub(indent)),
+                    None => vec![line.to_string()],
+                });
+                writeln!(
+                    w,
+                    "{blank:width$} {} {}",
+                    painter.paint(Style::Cyan, label),
+                    isolated(frame, &lines.next().unwrap_or_default())
+                )?;
+                for line in lines {
+                    writeln!(w, "{blank:indent$}{}", isolated(frame, &line))?;
+                }
+                // A trimmed box has no separator above its bottom corner
+                let last =
+                    index + 1 == footnotes.len() && frame.related.is_empty() && docs_url.is_none();
+                if !(frame.trim_surrounding && last) {
+                    writeln!(w, "{blank:width$} {separator}")?;
+                }
+            }
+
+            // Related locations are cited one per line in a single block
+            if !frame.related.is_empty() {
+                let label = theme.related_label();
+                for location in &frame.related {
+                    writeln!(
+                        w,
+                        "{blank:width$} {} {}",
+                        painter.paint(Style::Cyan, &label),
+                        isolated(frame, location)
+                    )?;
+                }
+                if !(frame.trim_surrounding && docs_url.is_none()) {
+                    writeln!(w, "{blank:width$} {separator}")?;
+                }
+            }
+
+            if let Some(url) = docs_url {
+                let shown = if frame.hyperlinks {
+                    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, url)
+                } else {
+                    url.to_string()
+                };
+                writeln!(
+                    w,
+                    "{blank:width$} {} {}",
+                    painter.paint(Style::Cyan, &theme.see_label()),
+                    shown
+                )?;
+                if !frame.trim_surrounding {
+                    writeln!(w, "{blank:width$} {separator}")?;
+                }
+            }
+            Ok(())
+        }
+        Segment::Bottom => writeln!(
+            w,
+            "{blank:width$} {}",
+            painter.paint(Style::Dim, theme.bottom)
+        ),
+    }
+}
+
+/// `text` wrapped in directional isolates if the frame asks for them and
+/// it contains right-to-left text
+fn isolated<'t>(frame: &Frame, text: &'t str) -> Cow<'t, str> {
+    if frame.isolate_bidi {
+        text::isolate_rtl(text)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Writer that drops spaces at the end of every line
+///
+/// Spaces are held back until something other than a newline follows them,
+/// so padding never leaves trailing whitespace on blank rows.
+struct TrimTrailing<'w, W: Write> {
+    inner: &'w mut W,
+    pending: usize,
+}
+
+impl<'w, W: Write> TrimTrailing<'w, W> {
+    fn new(inner: &'w mut W) -> Self {
+        Self { inner, pending: 0 }
+    }
+}
+
+impl<W: Write> Write for TrimTrailing<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut rest = s;
+        while let Some(index) = rest.find([' ', '\n']) {
+            let (text, tail) = rest.split_at(index);
+            if !text.is_empty() {
+                write!(self.inner, "{:1$}", "", self.pending)?;
+                self.pending = 0;
+                self.inner.write_str(text)?;
+            }
+            if tail.starts_with(' ') {
+                self.pending += 1;
+            } else {
+                self.pending = 0;
+                self.inner.write_char('\n')?;
+            }
+            rest = &tail[1..];
+        }
+        if !rest.is_empty() {
+            write!(self.inner, "{:1$}", "", self.pending)?;
+            self.pending = 0;
+            self.inner.write_str(rest)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        to_lsp_json, to_sarif, BoxStyle, ColorChoice, Label, Labels, RelatedLocation, Suggestion,
+        DEFAULT_MAX_LINE_WIDTH,
+    };
+
+    fn render_at(line: usize) -> String {
+        let source = "let x = 1;\n".repeat(line);
+        Diagnostic::builder()
+            .source(source)
+            .file("gutter.ts")
+            .kind("Type Error")
+            .line(line)
+            .col(5)
+            .message("msg")
+            .help("help")
+            .build()
+            .render()
+    }
+
+    /// Character column of `│` on every row that has one
+    fn bar_columns(rendered: &str) -> Vec<usize> {
+        rendered
+            .lines()
+            .filter_map(|line| line.chars().position(|c| c == '│'))
+            .collect()
+    }
+
+    #[test]
+    fn test_gutter_aligned_for_small_and_large_line_numbers() {
+        let small = render_at(5);
+        let large = render_at(99999);
+
+        let small_columns = bar_columns(&small);
+        assert_eq!(small_columns.len(), 5);
+        assert!(small_columns.iter().all(|&c| c == 4));
+        assert!(small.contains("\n  5 │ let x = 1;\n    │     ^ msg\n"));
+
+        let large_columns = bar_columns(&large);
+        assert_eq!(large_columns.len(), 5);
+        assert!(large_columns.iter().all(|&c| c == 6));
+        assert!(large.contains("\n99999 │ let x = 1;\n      │     ^ msg\n"));
+    }
+
+    #[test]
+    fn test_every_gutter_row_shares_one_column() {
+        for line in [1, 42, 123_456] {
+            let rendered = render_at(line);
+            let gutter = rendered
+                .lines()
+                .filter(|l| l.contains(['┌', '│', '=', '└']))
+                .map(|l| l.find(['┌', '│', '=', '└']).unwrap())
+                .collect::<Vec<_>>();
+
+            // Location, separator, source, caret, separator, help, separator, bottom
+            assert_eq!(gutter.len(), 8);
+            assert!(gutter.iter().all(|&column| column == gutter[0]));
+        }
+    }
+
+    fn numbered_rows(rendered: &str) -> Vec<&str> {
+        rendered
+            .lines()
+            .filter(|l| l.trim_start().starts_with(|c: char| c.is_ascii_digit()))
+            .collect()
+    }
+
+    #[test]
+    fn test_last_line_without_trailing_newline() {
+        let at_last = |source: &str, line| {
+            Diagnostic::builder()
+                .source(source)
+                .line(line)
+                .col(1)
+                .message("here")
+                .build()
+                .render()
+        };
+
+        let rendered = at_last("a\nb\nc", 3);
+        assert_eq!(numbered_rows(&rendered), ["  3 │ c"]);
+        assert!(rendered.contains("  3 │ c\n    │ ^ here\n"));
+
+        let single = at_last("c", 1);
+        assert!(single.contains("  1 │ c\n    │ ^ here\n"));
+
+        // A trailing newline doesn't add a line of its own
+        assert_eq!(at_last("a\nb\nc\n", 3), rendered);
+    }
+
+    #[test]
+    fn test_context_lines_near_start_of_file() {
+        let source = "line 1\nline 2\nline 3\nline 4\nline 5";
+        let rendered = Diagnostic::builder()
+            .source(source)
+            .file("context.ts")
+            .kind("Type Error")
+            .line(1)
+            .col(1)
+            .underline(4)
+            .message("msg")
+            .context_lines(2)
+            .build()
+            .render();
+
+        // Nothing exists above line 1, so only the lines below are shown
+        assert_eq!(
+            numbered_rows(&rendered),
+            ["  1 │ line 1", "  2 │ line 2", "  3 │ line 3"]
+        );
+        assert_eq!(rendered.matches('^').count(), 4);
+        assert!(rendered.contains("  1 │ line 1\n    │ ^^^^ msg\n  2 │ line 2\n"));
+    }
+
+    #[test]
+    fn test_context_lines_in_middle_of_file() {
+        let source: String = (1..=10).map(|n| format!("line {}\n", n)).collect();
+        let rendered = Diagnostic::builder()
+            .source(source)
+            .file("context.ts")
+            .kind("Type Error")
+            .line(5)
+            .col(6)
+            .message("msg")
+            .context_lines(2)
+            .build()
+            .render();
+
+        assert_eq!(
+            numbered_rows(&rendered),
+            [
+                "  3 │ line 3",
+                "  4 │ line 4",
+                "  5 │ line 5",
+                "  6 │ line 6",
+                "  7 │ line 7"
+            ]
+        );
+        // Only the error line gets a caret row
+        assert_eq!(rendered.matches('^').count(), 1);
+        assert!(rendered.contains("  5 │ line 5\n    │      ^ msg\n  6 │ line 6\n"));
+    }
+
+    #[test]
+    fn test_prev_line_shows_enclosing_statement() {
+        let source = "export function get(ctx) {\n  if (ctx.ok) {\n\n    eval(ctx.body);\n  }\n}";
+        let diagnostic = Diagnostic::builder()
+            .source(source)
+            .kind("Forbidden Global")
+            .line(4)
+            .col(5)
+            .underline(4)
+            .message("not allowed");
+
+        let rendered = diagnostic.clone().show_prev_line(true).build().render();
+        assert_eq!(
+            numbered_rows(&rendered),
+            ["  2 │   if (ctx.ok) {", "  4 │     eval(ctx.body);"]
+        );
+        assert!(rendered.contains(
+            "  2 │   if (ctx.ok) {\n  ⋮ │\n  4 │     eval(ctx.body);\n    │     ^^^^ not allowed\n"
+        ));
+        assert_eq!(rendered.matches('^').count(), 4);
+
+        // Off by default, and the first line has nothing above it
+        assert_eq!(numbered_rows(&diagnostic.clone().build().render()).len(), 1);
+        let first = diagnostic
+            .line(1)
+            .col(1)
+            .show_prev_line(true)
+            .build()
+            .render();
+        assert_eq!(numbered_rows(&first), ["  1 │ export function get(ctx) {"]);
+    }
+
+    #[test]
+    fn test_context_lines_at_end_of_file() {
+        let rendered = Diagnostic::builder()
+            .source("a\nb\nc")
+            .line(3)
+            .context_lines(3)
+            .build()
+            .render();
+
+        assert_eq!(numbered_rows(&rendered), ["  1 │ a", "  2 │ b", "  3 │ c"]);
+    }
+
+    #[test]
+    fn test_line_lookup_in_large_source() {
+        let source: String = (1..=10_000).map(|n| format!("line {}\n", n)).collect();
+
+        let line = |n| source_window(&source, n, n).next().map(|(_, l)| l);
+        assert_eq!(line(1), Some("line 1"));
+        assert_eq!(line(5_000), Some("line 5000"));
+        assert_eq!(line(10_000), Some("line 10000"));
+        assert_eq!(line(10_001), None);
+        assert_eq!(line(0), None);
+
+        let window: Vec<_> = source_window(&source, 9_999, 10_002).collect();
+        assert_eq!(window, [(9_999, "line 9999"), (10_000, "line 10000")]);
+
+        let rendered = Diagnostic::builder()
+            .source(source.as_str())
+            .line(7_321)
+            .context_lines(1)
+            .build()
+            .render();
+        assert_eq!(
+            numbered_rows(&rendered),
+            ["7320 │ line 7320", "7321 │ line 7321", "7322 │ line 7322"]
+        );
+    }
+
+    #[test]
+    fn test_long_line_truncated_around_error() {
+        let source = format!("const data = [{}]; oops();", "1, ".repeat(2_000));
+        let col = source.find("oops").unwrap() + 1;
+        let rendered = Diagnostic::builder()
+            .source(source.as_str())
+            .file("minified.ts")
+            .kind("Forbidden Call")
+            .line(1)
+            .col(col)
+            .underline(4)
+            .message("msg")
+            .build()
+            .render();
+
+        let source_row = rendered.lines().find(|l| l.starts_with("  1 │ ")).unwrap();
+        let shown = &source_row["  1 │ ".len()..];
+        assert!(shown.starts_with('…'));
+        assert!(shown.ends_with("oops();"));
+        assert_eq!(text::width(shown), DEFAULT_MAX_LINE_WIDTH);
+
+        // The caret still sits under the token inside the truncated window
+        let caret_row = rendered.lines().find(|l| l.contains('^')).unwrap();
+        let carets = caret_row["    │ ".len()..].find('^').unwrap();
+        let token = shown.find("oops").unwrap();
+        assert_eq!(carets, text::width(&shown[..token]));
+        assert!(caret_row.ends_with("^^^^ msg"));
+    }
+
+    #[test]
+    fn test_long_line_kept_when_limit_disabled() {
+        let source = "x".repeat(500);
+        let rendered = Diagnostic::builder()
+            .source(source.as_str())
+            .col(400)
+            .max_line_width(None)
+            .build()
+            .render();
+
+        assert!(rendered.contains(&source));
+        assert!(!rendered.contains('…'));
+    }
+
+    #[test]
+    fn test_ascii_box_style() {
+        let source = format!(
+            "import {{ console }} from 'tana/invalid'; {}",
+            "x".repeat(200)
+        );
+        let diagnostic = Diagnostic::builder()
+            .source(source.as_str())
+            .file("contract.ts")
+            .kind("Invalid Import")
+            .line(1)
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .context_lines(1)
+            .box_style(BoxStyle::Ascii);
+
+        let rendered = diagnostic.clone().build().render();
+        assert!(rendered.is_ascii());
+        assert!(rendered.contains("[error] Invalid Import"));
+        assert!(rendered.contains("\n    +- contract.ts:1:26\n"));
+        assert!(rendered.contains("  1 | import { console }"));
+        assert!(rendered.contains("    |                          ^^^^^^^^^^^^ Module"));
+        assert!(rendered.contains("...\n"));
+        assert!(rendered.ends_with("    |\n    +-\n"));
+
+        let warning = diagnostic.severity(Severity::Warning).build().render();
+        assert!(warning.is_ascii());
+        assert!(warning.contains("[warning] Invalid Import"));
+    }
+
+    #[test]
+    fn test_ascii_and_unicode_share_layout() {
+        let build = |style| {
+            Diagnostic::builder()
+                .source("let x = 1;")
+                .col(5)
+                .message("msg")
+                .box_style(style)
+                .build()
+                .render()
+        };
+        let to_ascii = |s: String| {
+            s.replace("┌─", "+-")
+                .replace("└─", "+-")
+                .replace('│', "|")
+                .replace("❌", "[error]")
+        };
+
+        assert_eq!(to_ascii(build(BoxStyle::Unicode)), build(BoxStyle::Ascii));
+    }
+
+    #[test]
+    fn test_multiline_message_keeps_gutter() {
+        let rendered = Diagnostic::builder()
+            .source("let total = price * qty;")
+            .kind("Type Error")
+            .col(13)
+            .underline(5)
+            .message("expected number\nfound string")
+            .help("Convert with Number(price)")
+            .build()
+            .render();
+
+        assert!(rendered.contains(
+            "    │             ^^^^^ expected number\n    │                   found string\n"
+        ));
+        assert!(rendered
+            .lines()
+            .filter(|l| l.contains("expected number") || l.contains("found string"))
+            .all(|l| l.starts_with("    │ ")));
+    }
+
+    #[test]
+    fn test_multiline_help_is_indented() {
+        let rendered = Diagnostic::builder()
+            .source("let x = 1;")
+            .message("msg")
+            .help("first step\nsecond step")
+            .build()
+            .render();
+
+        assert!(rendered.contains("    = help: first step\n            second step\n    │\n"));
+    }
+
+    #[test]
+    fn test_gutter_width_minimum() {
+        let row = |line_num| SnippetRow {
+            line_num,
+            text: String::new(),
+            marks: Vec::new(),
+        };
+
+        assert_eq!(gutter_width(&[row(1)]), 3);
+        assert_eq!(gutter_width(&[row(999), row(1000)]), 4);
+    }
+
+    #[test]
+    fn test_secondary_label_on_another_line() {
+        let source = "let count: number = 0;\nlet x = 1;\ncount = \"ten\";";
+        let rendered = Diagnostic::builder()
+            .source(source)
+            .file("types.ts")
+            .kind("Type Mismatch")
+            .line(3)
+            .col(9)
+            .underline(5)
+            .message("expected number, found string")
+            .secondary_label(Label::new(1, 12, 6, "declared as number here"))
+            .build()
+            .render();
+
+        // The label's line gets its own numbered row even outside the context
+        assert_eq!(
+            numbered_rows(&rendered),
+            ["  1 │ let count: number = 0;", "  3 │ count = \"ten\";"]
+        );
+        assert!(rendered.contains(
+            "  1 │ let count: number = 0;\n    │            ~~~~~~ declared as number here\n  ⋮ │\n"
+        ));
+        assert!(rendered.contains(
+            "  3 │ count = \"ten\";\n    │         ^^^^^ expected number, found string\n"
+        ));
+    }
+
+    #[test]
+    fn test_label_on_short_line_beside_truncated_error() {
+        let source = format!("let total = 0;\ntotal = [{}oops];", "1, ".repeat(100));
+        let col = source.lines().nth(1).unwrap().find("oops").unwrap() + 1;
+        let rendered = Diagnostic::builder()
+            .source(source.as_str())
+            .file("sum.ts")
+            .kind("Type Mismatch")
+            .line(2)
+            .col(col)
+            .underline(4)
+            .message("expected number")
+            .secondary_label(Label::new(1, 5, 5, "declared here"))
+            .build()
+            .render();
+
+        // The short line is shown whole, not cut at the long line's window
+        assert!(rendered.contains("  1 │ let total = 0;\n    │     ~~~~~ declared here\n"));
+        let error_row = rendered.lines().find(|l| l.starts_with("  2 │ ")).unwrap();
+        assert!(error_row.starts_with("  2 │ …") && error_row.ends_with("oops];"));
+        assert!(rendered.contains("^^^^ expected number"));
+    }
+
+    #[test]
+    fn test_overlapping_labels_get_their_own_rows() {
+        let rendered = Diagnostic::builder()
+            .source("let total = price * count;")
+            .col(13)
+            .underline(13)
+            .message("overflows")
+            .secondary_label(Label::new(1, 5, 13, "assigned here"))
+            .secondary_label(Label::new(1, 21, 5, "may be zero"))
+            .build()
+            .render();
+
+        // Ordered by start column, each padded from the line start alone
+        let underlines: Vec<&str> = rendered
+            .lines()
+            .filter(|l| l.contains(['^', '~']))
+            .collect();
+        assert_eq!(
+            underlines,
+            [
+                "    │     ~~~~~~~~~~~~~ assigned here",
+                "    │             ^^^^^^^^^^^^^ overflows",
+                "    │                     ~~~~~ may be zero",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_caret_follows_severity() {
+        let diagnostic = Diagnostic::builder()
+            .source("let unused = 1;")
+            .col(5)
+            .underline(6)
+            .message("never read");
+
+        let warning = diagnostic
+            .clone()
+            .severity(Severity::Warning)
+            .build()
+            .render();
+        assert!(warning.contains("  1 │ let unused = 1;\n    │     ~~~~~~ never read\n"));
+
+        let hint = diagnostic.clone().severity(Severity::Hint).build().render();
+        assert!(hint.contains("    │     ------ never read\n"));
+
+        let error = diagnostic.build().render();
+        assert!(error.contains("    │     ^^^^^^ never read\n"));
+
+        // Each diagnostic in a shared box keeps its own caret
+        let source = "let a = 1;\nlet b = 2;";
+        let at = |line, severity| {
+            Diagnostic::builder()
+                .source(source)
+                .file("a.ts")
+                .line(line)
+                .col(5)
+                .message("here")
+                .severity(severity)
+                .build()
+        };
+        let report = crate::format_file_report(&[at(1, Severity::Error), at(2, Severity::Warning)]);
+        assert!(report.contains("  1 │ let a = 1;\n    │     ^ here\n"));
+        assert!(report.contains("  2 │ let b = 2;\n    │     ~ here\n"));
+    }
+
+    #[test]
+    fn test_secondary_label_on_primary_line() {
+        let rendered = Diagnostic::builder()
+            .source("let a = b + c;")
+            .line(1)
+            .col(9)
+            .message("primary")
+            .secondary_label(Label::new(1, 13, 1, "secondary"))
+            .build()
+            .render();
+
+        assert!(rendered.contains(
+            "  1 │ let a = b + c;\n    │         ^ primary\n    │             ~ secondary\n"
+        ));
+    }
+
+    #[test]
+    fn test_no_line_ends_with_a_space() {
+        let rendered = Diagnostic::builder()
+            .source("let x = 1;   \n\n\tlet y = 2;\n")
+            .kind("Type Error")
+            .line(2)
+            .message("first \nsecond ")
+            .help("step one \n\nstep two ")
+            .context_lines(1)
+            .build()
+            .render();
+
+        assert!(rendered.lines().all(|line| !line.ends_with(' ')));
+        assert!(rendered.contains("  1 │ let x = 1;\n  2 │\n    │ ^ first\n    │   second\n"));
+        assert!(rendered.contains("    = help: step one\n\n            step two\n"));
+    }
+
+    #[test]
+    fn test_trim_trailing_keeps_inner_spaces() {
+        let mut out = String::new();
+        let mut w = TrimTrailing::new(&mut out);
+        write!(w, "a  b ").unwrap();
+        write!(w, "  \n   \n  c").unwrap();
+
+        assert_eq!(out, "a  b\n\n  c");
+    }
+
+    #[test]
+    fn test_custom_theme_substitutes_chrome() {
+        let theme = Theme {
+            error_icon: "",
+            labels: Labels {
+                error_title: "Contract Error",
+                help: "fix",
+                ..Labels::english()
+            },
+            gutter_char: '┃',
+            caret_char: '~',
+            ..Theme::default()
+        };
+        let rendered = Diagnostic::builder()
+            .source("let x = 1;")
+            .kind("Type Error")
+            .col(5)
+            .message("msg")
+            .help("first\nsecond")
+            .build()
+            .render_with(&theme);
+
+        assert!(rendered.starts_with("\nContract Error\nType Error\n"));
+        assert!(rendered.contains("  1 ┃ let x = 1;\n    ┃     ~ msg\n"));
+        assert!(rendered.contains("    = fix: first\n           second\n"));
+        assert!(!rendered.contains('^'));
+    }
+
+    #[test]
+    fn test_empty_gutter_rows_can_drop_their_separator() {
+        let theme = Theme {
+            empty_gutter_char: Some(' '),
+            ..Theme::default()
+        };
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;")
+            .file("contract.ts")
+            .kind("Type Error")
+            .col(5)
+            .message("msg")
+            .help("help")
+            .build();
+        let rendered = diagnostic.render_with(&theme);
+
+        assert_eq!(
+            rendered,
+            "\nValidation Error\n❌ Type Error\n\n    ┌─ contract.ts:1:5\n\n  1 │ let x = 1;\n    │     ^ msg\n\n    = help: help\n\n    └─\n"
+        );
+
+        // Content rows keep the gutter, and the default follows it
+        let pipes = Theme {
+            empty_gutter_char: Some('|'),
+            ..Theme::default()
+        };
+        assert!(diagnostic
+            .render_with(&pipes)
+            .contains("  1 │ let x = 1;\n    │     ^ msg\n    |\n"));
+        assert_eq!(
+            diagnostic.render_with(&Theme::default()),
+            diagnostic.render()
+        );
+    }
+
+    #[test]
+    fn test_default_theme_matches_render() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;")
+            .message("msg")
+            .help("help")
+            .build();
+
+        assert_eq!(
+            diagnostic.render_with(&Theme::default()),
+            diagnostic.render()
+        );
+    }
+
+    #[test]
+    fn test_spanish_labels_keep_layout() {
+        let spanish = Theme {
+            labels: Labels {
+                error_title: "Error de validación",
+                help: "ayuda",
+                ..Labels::english()
+            },
+            ..Theme::default()
+        };
+        let diagnostic = Diagnostic::builder()
+            .source("import { console } from 'tana/invalid';")
+            .file("contract.ts")
+            .kind("Invalid Import")
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core")
+            .build();
+
+        let english = diagnostic.render();
+        let localized = diagnostic.render_with(&spanish);
+        assert!(localized.starts_with("\nError de validación\n"));
+        assert!(localized.contains("    = ayuda: Available modules: tana/core\n"));
+        assert_eq!(
+            localized
+                .replace("Error de validación", "Validation Error")
+                .replace("= ayuda:", "= help:"),
+            english
+        );
+    }
+
+    #[test]
+    fn test_help_wraps_at_wrap_width() {
+        let rendered = Diagnostic::builder()
+            .source("import { x } from 'tana/nope';")
+            .message("msg")
+            .help("Available modules: tana/core, tana/kv, tana/block, tana/tx, tana/utils")
+            .wrap_width(40)
+            .build()
+            .render();
+
+        let help: Vec<&str> = rendered
+            .lines()
+            .skip_while(|line| !line.contains("= help:"))
+            .take_while(|line| !line.ends_with('│'))
+            .collect();
+        assert_eq!(
+            help,
+            [
+                "    = help: Available modules:",
+                "            tana/core, tana/kv,",
+                "            tana/block, tana/tx,",
+                "            tana/utils",
+            ]
+        );
+        assert!(help.iter().all(|line| text::width(line) <= 40));
+    }
+
+    #[test]
+    fn test_location_hyperlink_only_when_enabled() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;")
+            .file("/work/my contract.ts")
+            .line(1)
+            .col(5);
+
+        let plain = diagnostic.clone().build().render();
+        assert!(!plain.contains("\x1b]8;;"));
+        assert!(plain.contains("┌─ /work/my contract.ts:1:5\n"));
+
+        let vscode = diagnostic
+            .clone()
+            .hyperlinks(Hyperlinks::Vscode)
+            .build()
+            .render();
+        assert!(vscode.contains(
+            "┌─ \x1b]8;;vscode://file/work/my%20contract.ts:1:5\x1b\\/work/my contract.ts:1:5\x1b]8;;\x1b\\\n"
+        ));
+
+        let file = diagnostic.hyperlinks(Hyperlinks::File).build().render();
+        assert!(file.contains("\x1b]8;;file:///work/my%20contract.ts\x1b\\"));
+    }
+
+    #[test]
+    fn test_relative_path_links_only_under_a_workspace_root() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1;")
+            .file("contracts/a#1.ts")
+            .col(5)
+            .hyperlinks(Hyperlinks::File);
+
+        let unrooted = diagnostic.clone().build().render();
+        assert!(!unrooted.contains("\x1b]8;;"));
+        assert!(unrooted.contains("┌─ contracts/a#1.ts:1:5\n"));
+
+        let rooted = diagnostic.workspace_root("/work").build().render();
+        assert!(rooted.contains("\x1b]8;;file:///work/contracts/a%231.ts\x1b\\"));
+    }
+
+    #[test]
+    fn test_workspace_root_relativizes_location() {
+        let at = |path: &str| {
+            Diagnostic::builder()
+                .source("let x = 1;")
+                .file(path)
+                .col(5)
+                .workspace_root("/home/dev/tana/")
+                .hyperlinks(Hyperlinks::File)
+                .build()
+        };
+
+        let inside = at("/home/dev/tana/contracts/foo.ts");
+        let rendered = inside.render();
+        assert!(rendered.contains("\x1b\\contracts/foo.ts:1:5\x1b]8;;"));
+        // The stored path, and the link built from it, stay absolute
+        assert!(rendered.contains("file:///home/dev/tana/contracts/foo.ts"));
+        assert_eq!(inside.file_path, "/home/dev/tana/contracts/foo.ts");
+
+        // Only whole directories match, so a sibling with a shared prefix is kept
+        for outside in ["/tmp/foo.ts", "/home/dev/tana-old/foo.ts"] {
+            assert!(at(outside).render().contains(&format!("\\{}:1:5", outside)));
+        }
+    }
+
+    #[test]
+    fn test_leading_bom_is_stripped() {
+        let render = |source: &str| {
+            Diagnostic::builder()
+                .source(source)
+                .col(5)
+                .underline(1)
+                .message("here")
+                .build()
+                .render()
+        };
+        let plain = render("let x = 1;");
+
+        assert_eq!(render("\u{FEFF}let x = 1;"), plain);
+        assert!(plain.contains("  1 │ let x = 1;\n    │     ^ here\n"));
+
+        // Byte offsets counted from the start of the file land on the same column
+        let code = "\u{FEFF}let x = 1;";
+        let from_offset = Diagnostic::from_byte_span(code, "", "", 7, 8, "here", "");
+        assert_eq!((from_offset.span.line, from_offset.span.col), (1, 5));
+        assert_eq!(from_offset.render(), plain);
+    }
+
+    #[test]
+    fn test_render_width_fits_every_row() {
+        let source = format!("const config = {{ {} }};", "key: 'value', ".repeat(12));
+        let rendered = Diagnostic::builder()
+            .source(&source)
+            .file("config.ts")
+            .col(90)
+            .underline(3)
+            .message("this key is already defined earlier in the same object literal")
+            .help("object keys must be unique; remove or rename the duplicated key so the earlier value is not overwritten")
+            .render_width(60)
+            .build()
+            .render();
+
+        for line in rendered.lines() {
+            assert!(text::width(line) <= 60, "too wide: {:?}", line);
+        }
+        assert!(rendered.contains('…'));
+        assert!(rendered.contains("    = help: object keys must be unique; remove or rename the\n"));
+
+        // Without a render width the same rows run long
+        let unbounded = Diagnostic::builder()
+            .source(&source)
+            .col(90)
+            .build()
+            .render();
+        assert!(unbounded.lines().any(|line| text::width(line) > 60));
+    }
+
+    #[test]
+    fn test_error_code_in_header() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x: number = \"1\";")
+            .kind("Type Error")
+            .col(17);
+
+        let with_code = diagnostic.clone().code("T0308").build();
+        assert!(with_code.render().contains("\n❌ Type Error [T0308]\n"));
+        assert!(to_lsp_json(&with_code).contains(r#""code":"T0308""#));
+        assert!(to_sarif(std::slice::from_ref(&with_code)).contains(r#""ruleId":"T0308""#));
+
+        let without = diagnostic.build();
+        assert!(without.render().contains("\n❌ Type Error\n"));
+        assert!(!to_lsp_json(&without).contains(r#""code""#));
+        assert!(to_sarif(&[without]).contains(r#""ruleId":"Type Error""#));
+    }
+
+    #[test]
+    fn test_rtl_text_is_isolated_when_enabled() {
+        let diagnostic = Diagnostic::builder()
+            .source("const name = \"שלום\";")
+            .line(1)
+            .col(14)
+            .underline(6)
+            .message("הודעה לא תקינה")
+            .help("use an ASCII name");
+
+        let isolated = diagnostic.clone().isolate_bidi(true).build().render();
+        assert!(isolated.contains("  1 │ \u{2066}const name = \"שלום\";\u{2069}\n"));
+        assert!(isolated.contains("│              ^^^^^^ \u{2066}הודעה לא תקינה\u{2069}\n"));
+        // Left-to-right text is left alone
+        assert!(isolated.contains("= help: use an ASCII name\n"));
+
+        let plain = diagnostic.build().render();
+        assert!(!plain.contains(['\u{2066}', '\u{2069}']));
+    }
+
+    #[test]
+    fn test_control_characters_in_message_and_help_are_neutralized() {
+        let rendered = Diagnostic::builder()
+            .source("let x = 1;")
+            .col(5)
+            .message("cleared\x1b[2J\x07")
+            .help("first\r\nsecond\x1b]8;;evil\x1b\\")
+            .build()
+            .render();
+
+        assert!(!rendered.contains(['\x1b', '\x07', '\r']));
+        assert!(rendered.contains("^ cleared␛[2J␇\n"));
+        assert!(rendered.contains("= help: first\n"));
+        assert!(rendered.contains("second␛]8;;evil␛\\\n"));
+    }
+
+    #[test]
+    fn test_control_characters_in_source_line_are_shown() {
+        let rendered = Diagnostic::builder()
+            .source("let a\x07 = bell;")
+            .col(10)
+            .underline(4)
+            .message("unknown name")
+            .build()
+            .render();
+
+        assert!(!rendered.contains('\x07'));
+        assert!(rendered.contains("  1 │ let a␇ = bell;\n"));
+        assert!(rendered.contains("    │          ^^^^ unknown name\n"));
+    }
+
+    #[test]
+    fn test_type_mismatch_aligns_expected_and_found() {
+        let diagnostic = Diagnostic::builder()
+            .source("const total: number = '12';")
+            .kind("Type Error")
+            .col(23)
+            .underline(4)
+            .message("mismatched types")
+            .help("parse the string first")
+            .type_mismatch(TypeMismatch::new("number", "string"));
+
+        let rendered = diagnostic.clone().build().render();
+        assert!(rendered.contains(
+            "    │                       ^^^^ mismatched types\n    │\n    │ expected: number\n    │    found: string\n    │\n    = help: parse the string first\n"
+        ));
+
+        let colored = diagnostic.color(ColorChoice::Always).build().render();
+        assert!(colored.contains("expected: \x1b[32mnumber\x1b[0m\n"));
+        assert!(colored.contains("found: \x1b[31mstring\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_related_locations_are_cited_in_order() {
+        let rendered = Diagnostic::builder()
+            .source("const price: Price = '10';")
+            .file("contract.ts")
+            .kind("Type Error")
+            .col(22)
+            .underline(4)
+            .message("expected number, found string")
+            .help("remove the quotes")
+            .related(RelatedLocation::new(
+                "types.ts",
+                10,
+                5,
+                "Price defined here",
+            ))
+            .related(RelatedLocation::new(
+                "lib/money.ts",
+                3,
+                1,
+                "Price aliased here",
+            ))
+            .docs_url("https://docs.tana.network/errors/type-error")
+            .build()
+            .render();
+
+        assert!(rendered.contains(
+            "    = help: remove the quotes\n    │\n    = related: types.ts:10:5: Price defined here\n    = related: lib/money.ts:3:1: Price aliased here\n    │\n    = see https://docs.tana.network/errors/type-error\n"
+        ));
+        assert!(!rendered.contains("  10 │"));
+    }
+
+    #[test]
+    fn test_docs_url_footer_only_when_supplied() {
+        let diagnostic = Diagnostic::builder()
+            .source("import { x } from 'tana/nope';")
+            .file("contract.ts")
+            .kind("Invalid Import")
+            .help("check the module name")
+            .note("modules are case-sensitive");
+        let url = "https://docs.tana.network/errors/invalid-import";
+
+        let with_url = diagnostic.clone().docs_url(url).build().render();
+        assert!(with_url.ends_with(&format!(
+            "    = note: modules are case-sensitive\n    │\n    = see {}\n    │\n    └─\n",
+            url
+        )));
+        assert!(!diagnostic.clone().build().render().contains("= see"));
+
+        let linked = diagnostic
+            .docs_url(url)
+            .hyperlinks(Hyperlinks::File)
+            .build()
+            .render();
+        assert!(linked.contains(&format!(
+            "= see \x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\\n",
+            url, url
+        )));
+    }
+
+    #[test]
+    fn test_out_of_bounds_line_shows_placeholder() {
+        let rendered = Diagnostic::builder()
+            .source("only one line")
+            .line(999)
+            .col(4)
+            .underline(5)
+            .message("unexpected end of input")
+            .build()
+            .render();
+
+        assert!(rendered.contains("999 │ <line not available>\n    │ unexpected end of input\n"));
+        assert!(!rendered.contains('^'));
+        assert!(!rendered.contains("only one line"));
+    }
+
+    #[test]
+    fn test_empty_source_has_an_empty_first_line() {
+        let diagnostic = crate::check_entry_point("", "default").unwrap();
+        let rendered = diagnostic.render();
+
+        assert!(!rendered.contains("<line not available>"));
+        assert!(rendered.contains("  1 │\n    │ ^ contract has no default export\n"));
+    }
+
+    #[test]
+    fn test_overlong_underline_stops_at_line_end() {
+        let rendered = Diagnostic::builder()
+            .source("let x = 1;")
+            .col(9)
+            .underline(40)
+            .message("msg")
+            .build()
+            .render();
+
+        assert!(rendered.contains("  1 │ let x = 1;\n    │         ^^ msg\n"));
+    }
+
+    #[test]
+    fn test_column_past_line_end_points_after_last_character() {
+        let rendered = Diagnostic::builder()
+            .source("let x = 1\nnext")
+            .col(30)
+            .underline(3)
+            .message("expected `;`")
+            .build()
+            .render();
+
+        assert!(rendered.contains("  1 │ let x = 1\n    │          ^ expected `;`\n"));
+    }
+
+    #[test]
+    fn test_column_annotation_only_when_enabled() {
+        let diagnostic = Diagnostic::builder()
+            .source("import { console } from 'tana/invalid';")
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .secondary_label(Label::new(1, 10, 7, "imported here"));
+
+        let shown = diagnostic.clone().show_columns(true).build().render();
+        let carets = format!("    │ {}^^^^^^^^^^^^ ", " ".repeat(25));
+        assert!(shown.contains(&format!(
+            "{}Module 'tana/invalid' not found (col 26)\n",
+            carets
+        )));
+        assert!(shown.contains("    │          ~~~~~~~ imported here (col 10)\n"));
+
+        let plain = diagnostic.build().render();
+        assert!(plain.contains(&format!("{}Module 'tana/invalid' not found\n", carets)));
+        assert!(!plain.contains("(col"));
+
+        let span = Diagnostic::builder()
+            .source("const a = {\n  b: 1,\n")
+            .span(crate::Span::range(1, 11, 2, 8))
+            .message("unterminated")
+            .show_columns(true)
+            .build()
+            .render();
+        assert!(span.contains("    │ ^^^^^^^ unterminated (col 11, end col 8)\n"));
+        assert_eq!(span.matches("(col").count(), 1);
+    }
+
+    #[test]
+    fn test_line_offset_numbers_rows_as_in_document() {
+        let rendered = Diagnostic::builder()
+            .source("const a = 1;\nconst b = c;\nconst d = 2;")
+            .file("README.md")
+            .line(2)
+            .col(11)
+            .message("'c' is not defined")
+            .context_lines(1)
+            .line_offset(41)
+            .build()
+            .render();
+
+        assert!(rendered.contains("┌─ README.md:43:11\n"));
+        assert_eq!(
+            numbered_rows(&rendered),
+            [
+                " 42 │ const a = 1;",
+                " 43 │ const b = c;",
+                " 44 │ const d = 2;"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_label_below_starts_under_first_caret() {
+        let diagnostic = Diagnostic::builder()
+            .source("import { console } from 'tana/invalid';")
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found");
+
+        let below = diagnostic
+            .clone()
+            .label_placement(LabelPlacement::Below)
+            .build()
+            .render();
+        let rows: Vec<&str> = below.lines().collect();
+        let carets = rows.iter().position(|row| row.contains('^')).unwrap();
+        let indent = " ".repeat(25);
+        assert_eq!(rows[carets], format!("    │ {}^^^^^^^^^^^^", indent));
+        assert_eq!(
+            rows[carets + 1],
+            format!("    │ {}Module 'tana/invalid' not found", indent)
+        );
+
+        let inline = diagnostic.build().render();
+        assert!(inline.contains("^^^^^^^^^^^^ Module 'tana/invalid' not found\n"));
+    }
+
+    #[test]
+    fn test_trim_surrounding_drops_leading_line_and_closing_separator() {
+        let diagnostic = Diagnostic::builder()
+            .source("let x = 1")
+            .file("a.ts")
+            .kind("Syntax Error")
+            .col(10)
+            .message("expected `;`");
+
+        let framed = diagnostic.clone().build().render();
+        let trimmed = diagnostic.clone().trim_surrounding(true).build().render();
+        assert_eq!(
+            framed,
+            "\nValidation Error\n❌ Syntax Error\n\n    ┌─ a.ts:1:10\n    │\n  1 │ let x = 1\n    │          ^ expected `;`\n    │\n    └─\n"
+        );
+        assert_eq!(
+            trimmed,
+            "Validation Error\n❌ Syntax Error\n\n    ┌─ a.ts:1:10\n    │\n  1 │ let x = 1\n    │          ^ expected `;`\n    └─\n"
+        );
+
+        // Only the separator closing the last block goes
+        let with_help = diagnostic
+            .help("add a semicolon")
+            .note("statements end with `;`")
+            .trim_surrounding(true)
+            .build()
+            .render();
+        assert!(with_help.ends_with(
+            "    │          ^ expected `;`\n    │\n    = help: add a semicolon\n    │\n    = note: statements end with `;`\n    └─\n"
+        ));
+    }
+
+    #[test]
+    fn test_auto_placement_measures_message_in_display_columns() {
+        // Ten characters would fit after the caret, but they take twenty columns
+        let message = "名前が見つかりません";
+        let diagnostic = Diagnostic::builder()
+            .source("let x = y;")
+            .col(9)
+            .message(message)
+            .render_width(31)
+            .label_placement(LabelPlacement::Auto);
+
+        let rendered = diagnostic.clone().build().render();
+        assert!(rendered.contains(&format!("    │         ^\n    │         {}\n", message)));
+
+        let wide = diagnostic.render_width(40).build().render();
+        assert!(wide.contains(&format!("    │         ^ {}\n", message)));
+    }
+
+    #[test]
+    fn test_note_follows_help() {
+        let rendered = Diagnostic::builder()
+            .source("kv.put(key, value)")
+            .kind("Type Error")
+            .message("msg")
+            .help("pass a string key")
+            .note("keys are hashed before storage")
+            .build()
+            .render();
+
+        assert!(rendered.ends_with(
+            "    │\n    = help: pass a string key\n    │\n    = note: keys are hashed before storage\n    │\n    └─\n"
+        ));
+
+        let note_only = Diagnostic::builder()
+            .message("msg")
+            .note("only a note")
+            .build()
+            .render();
+        assert!(!note_only.contains("= help"));
+        assert!(note_only.contains("    │\n    = note: only a note\n    │\n    └─\n"));
+    }
+
+    #[test]
+    fn test_suggestion_shows_replacement_line() {
+        let source = "import { kv } from 'tana/invalid';";
+        let diagnostic = Diagnostic::builder()
+            .source(source)
+            .kind("Invalid Import")
+            .col(21)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("did you mean 'tana/kv'?");
+
+        let plain = diagnostic.clone().build().render();
+        assert!(!plain.contains("suggestion"));
+
+        let rendered = diagnostic
+            .suggestion(Suggestion::new(1, 21, 12, "tana/kv"))
+            .build()
+            .render();
+        assert!(rendered.contains(
+            "    │\n    = suggestion:\n  1 - import { kv } from 'tana/invalid';\n  1 + import { kv } from 'tana/kv';\n    │\n    = help:"
+        ));
+        // The snippet above the suggestion is untouched
+        assert!(rendered.starts_with(&plain[..plain.find("    = help:").unwrap()]));
+    }
+}
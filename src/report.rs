@@ -0,0 +1,614 @@
+//! Rendering several diagnostics together as one report
+
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use crate::{render, BoxStyle, Diagnostic, Labels, Severity, Theme};
+
+/// Render several diagnostics as a single report
+///
+/// The report opens with one title chosen from the most severe diagnostic
+/// (e.g. "Validation Error"), then prints each
+/// diagnostic's snippet separated by a blank line, and closes with a
+/// summary counting each severity, such as `= 2 errors, 1 warning found`.
+/// Severities with no diagnostics are left out of the summary. Diagnostics
+/// are printed in their [`Ord`] order: by file path, then line, then
+/// column, then severity, with the remaining fields breaking ties.
+///
+/// An empty slice renders as just the summary, `= no issues found`.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_report, Diagnostic};
+///
+/// let diagnostics = vec![
+///     Diagnostic::builder().file("b.ts").kind("Type Error").build(),
+///     Diagnostic::builder().file("a.ts").kind("Invalid Import").build(),
+/// ];
+///
+/// let report = format_report(&diagnostics);
+/// assert!(report.find("a.ts").unwrap() < report.find("b.ts").unwrap());
+/// assert!(report.ends_with("= 2 errors found\n"));
+/// ```
+pub fn format_report(diagnostics: &[Diagnostic]) -> String {
+    format_report_with_limit(diagnostics, None)
+}
+
+/// Render a report of at most `max_errors` diagnostics
+///
+/// Like [`format_report`], but once sorted only the first `max_errors`
+/// diagnostics are rendered, so a badly broken contract doesn't flood the
+/// terminal. When some are left out, a notice such as
+/// `= ... and 42 more errors not shown` precedes the summary, which still
+/// counts every diagnostic. `None` renders them all.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_report_with_limit, Diagnostic};
+///
+/// let diagnostics: Vec<Diagnostic> = (1..=5)
+///     .map(|line| Diagnostic::builder().line(line).message(format!("error {}", line)).build())
+///     .collect();
+///
+/// let report = format_report_with_limit(&diagnostics, Some(2));
+/// assert!(report.contains("error 2") && !report.contains("error 3"));
+/// assert!(report.ends_with("= ... and 3 more errors not shown\n= 5 errors found\n"));
+/// ```
+pub fn format_report_with_limit(diagnostics: &[Diagnostic], max_errors: Option<usize>) -> String {
+    if diagnostics.is_empty() {
+        let labels = BoxStyle::default().theme().labels;
+        return format!("\n= {}\n", labels.no_issues);
+    }
+
+    let sorted = sorted(diagnostics);
+    let shown = &sorted[..max_errors.unwrap_or(usize::MAX).min(sorted.len())];
+    let groups: Vec<&[&Diagnostic]> = shown.chunks(1).collect();
+
+    let mut out = String::new();
+    write_report(&mut out, &sorted, &groups, None).expect("writing to a String cannot fail");
+    out
+}
+
+/// Render several diagnostics as a report with one box per file
+///
+/// Like [`format_report`], but neighbouring diagnostics of a file share a
+/// single snippet box, located at the first of them, with every diagnostic
+/// underlined in place and their helps listed together. Only diagnostics
+/// with the same kind, code, docs link, suggestion and type mismatch are
+/// merged, since the box shows those once; a diagnostic differing in any of
+/// them starts a new box. Lines
+/// between two diagnostics are printed when the gap is at most twice the
+/// context, so close errors read as one excerpt; a larger gap collapses to
+/// a single `⋮` row, as rustc does for distant spans.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_file_report, Diagnostic};
+///
+/// let source = "let a = 1;\n".repeat(50);
+/// let at = |line| Diagnostic::builder().source(&source).file("a.ts").line(line).build();
+///
+/// let report = format_file_report(&[at(2), at(40)]);
+/// assert_eq!(report.matches("┌─").count(), 1);
+/// assert!(report.contains("  2 │ let a = 1;\n    │ ^\n  ⋮ │\n 40 │ let a = 1;\n"));
+/// ```
+pub fn format_file_report(diagnostics: &[Diagnostic]) -> String {
+    if diagnostics.is_empty() {
+        return format_report(diagnostics);
+    }
+
+    let sorted = sorted(diagnostics);
+    let groups: Vec<&[&Diagnostic]> = sorted.chunk_by(|a, b| shares_box(a, b)).collect();
+
+    let mut out = String::new();
+    write_report(&mut out, &sorted, &groups, None).expect("writing to a String cannot fail");
+    out
+}
+
+/// Render several diagnostics as a report with a header per file
+///
+/// Like [`format_report`], but files are listed alphabetically, each under
+/// a single `── contracts/foo.ts ──` header, with that file's diagnostics
+/// beneath it in order of position. The path is left out of each box's
+/// location line, which shows just the line and column.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{format_grouped_report, Diagnostic};
+///
+/// let at = |file, line| Diagnostic::builder().file(file).line(line).build();
+///
+/// let report = format_grouped_report(&[at("b.ts", 1), at("a.ts", 2), at("a.ts", 1)]);
+/// assert_eq!(report.matches("── a.ts ──").count(), 1);
+/// assert!(report.find("── a.ts ──").unwrap() < report.find("── b.ts ──").unwrap());
+/// ```
+pub fn format_grouped_report(diagnostics: &[Diagnostic]) -> String {
+    if diagnostics.is_empty() {
+        return format_report(diagnostics);
+    }
+
+    let sorted = sorted(diagnostics);
+    let mut out = String::new();
+    write_grouped_report(&mut out, &sorted).expect("writing to a String cannot fail");
+    out
+}
+
+/// Render a report like [`format_report`], drawn entirely with `theme`
+pub(crate) fn format_report_with_theme(diagnostics: &[Diagnostic], theme: &Theme) -> String {
+    if diagnostics.is_empty() {
+        return format!("\n= {}\n", theme.labels.no_issues);
+    }
+
+    let sorted = sorted(diagnostics);
+    let groups: Vec<&[&Diagnostic]> = sorted.chunks(1).collect();
+
+    let mut out = String::new();
+    write_report(&mut out, &sorted, &groups, Some(theme)).expect("writing to a String cannot fail");
+    out
+}
+
+/// Remove repeated diagnostics, keeping the first of each in its place
+///
+/// Two diagnostics are duplicates when they share a file, line, column,
+/// kind and message, as when several validation passes report the same
+/// problem. Help, notes and every other field are ignored; the first
+/// diagnostic's are kept.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{dedupe, Diagnostic};
+///
+/// let unused = Diagnostic::builder().file("a.ts").message("unused").build();
+/// let mut diagnostics = vec![unused.clone(), unused];
+///
+/// dedupe(&mut diagnostics);
+/// assert_eq!(diagnostics.len(), 1);
+/// ```
+pub fn dedupe(diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen = HashSet::new();
+    diagnostics.retain(|d| seen.insert(dedupe_key(d)));
+}
+
+/// Remove repeated diagnostics like [`dedupe`], merging their help into the
+/// first of each
+///
+/// Help lines of a duplicate that the kept diagnostic doesn't already have
+/// are appended to its help in order, so complementary help, such as one
+/// pass suggesting a module and another linking docs, isn't lost. Notes
+/// and every other field are still taken from the first diagnostic.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{dedupe_merging_help, Diagnostic};
+///
+/// let unused = Diagnostic::builder().file("a.ts").message("unused");
+/// let mut diagnostics = vec![
+///     unused.clone().help("remove it").build(),
+///     unused.help("or prefix it with _").build(),
+/// ];
+///
+/// dedupe_merging_help(&mut diagnostics);
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].help.as_deref(), Some("remove it\nor prefix it with _"));
+/// ```
+pub fn dedupe_merging_help(diagnostics: &mut Vec<Diagnostic>) {
+    let mut kept: HashMap<_, usize> = HashMap::new();
+    let mut merged: Vec<Diagnostic> = Vec::with_capacity(diagnostics.len());
+    for diagnostic in diagnostics.drain(..) {
+        match kept.entry(dedupe_key(&diagnostic)) {
+            Entry::Occupied(entry) => {
+                merge_help(&mut merged[*entry.get()], diagnostic.help.as_deref())
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(merged.len());
+                merged.push(diagnostic);
+            }
+        }
+    }
+    *diagnostics = merged;
+}
+
+/// What two diagnostics share when one repeats the other
+fn dedupe_key(d: &Diagnostic) -> (String, usize, usize, Cow<'static, str>, Cow<'static, str>) {
+    (
+        d.file_path.clone(),
+        d.span.line,
+        d.span.col,
+        d.kind.clone(),
+        d.message.clone(),
+    )
+}
+
+/// Append the non-empty lines of `help` that `into`'s help lacks
+fn merge_help(into: &mut Diagnostic, help: Option<&str>) {
+    let mut lines: Vec<String> = into
+        .help
+        .as_deref()
+        .map_or_else(Vec::new, |help| help.lines().map(str::to_string).collect());
+    let before = lines.len();
+    for line in help.into_iter().flat_map(str::lines) {
+        if !line.is_empty() && !lines.iter().any(|kept| kept == line) {
+            lines.push(line.to_string());
+        }
+    }
+    if lines.len() > before {
+        into.help = Some(lines.join("\n").into());
+    }
+}
+
+/// Whether `a` and `b` can be drawn in one box without losing what the box
+/// shows only once
+fn shares_box(a: &Diagnostic, b: &Diagnostic) -> bool {
+    a.file_path == b.file_path
+        && a.kind == b.kind
+        && a.code == b.code
+        && a.docs_url == b.docs_url
+        && a.suggestion == b.suggestion
+        && a.type_mismatch == b.type_mismatch
+}
+
+/// Diagnostics in their [`Ord`] order
+fn sorted(diagnostics: &[Diagnostic]) -> Vec<&Diagnostic> {
+    let mut sorted: Vec<&Diagnostic> = diagnostics.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+/// Write the title, one box per group, and the summary of `all`
+///
+/// Diagnostics in `all` but in no group are counted as not shown.
+///
+/// With a `theme`, every part of the report is drawn with it.
+fn write_report<W: Write>(
+    w: &mut W,
+    all: &[&Diagnostic],
+    groups: &[&[&Diagnostic]],
+    theme: Option<&Theme>,
+) -> fmt::Result {
+    // Title the report after its most severe diagnostic
+    let severity = all.iter().map(|d| d.severity).min().unwrap_or_default();
+
+    // Without a theme, the title and summary follow the first diagnostic's
+    // style and each body keeps its own
+    let style_theme = |d: &Diagnostic| d.options.box_style.theme();
+    let report_theme = theme.copied().unwrap_or_else(|| style_theme(all[0]));
+    render::write_title(w, severity, &report_theme)?;
+
+    for (index, group) in groups.iter().enumerate() {
+        if index > 0 {
+            writeln!(w)?;
+        }
+        let body_theme = theme.copied().unwrap_or_else(|| style_theme(group[0]));
+        render::write_group_body(w, group, &body_theme)?;
+    }
+    writeln!(w)?;
+
+    let shown: usize = groups.iter().map(|group| group.len()).sum();
+    let hidden = all.len() - shown;
+    if hidden > 0 {
+        let noun = if hidden == 1 {
+            report_theme.labels.error
+        } else {
+            report_theme.labels.errors
+        };
+        let notice = report_theme
+            .labels
+            .not_shown
+            .replace("{count}", &hidden.to_string())
+            .replace("{noun}", noun);
+        writeln!(w, "= {}", notice)?;
+    }
+
+    writeln!(w, "= {}", summary(all, &report_theme.labels))
+}
+
+/// Write the title, a header and boxes for each file, and the summary
+///
+/// `sorted` must already be ordered by file path.
+fn write_grouped_report<W: Write>(w: &mut W, sorted: &[&Diagnostic]) -> fmt::Result {
+    let severity = sorted.iter().map(|d| d.severity).min().unwrap_or_default();
+    let report_theme = sorted[0].options.box_style.theme();
+    render::write_title(w, severity, &report_theme)?;
+
+    for file in sorted.chunk_by(|a, b| a.file_path == b.file_path) {
+        let path = file[0].options.display_path(&file[0].file_path);
+        writeln!(w, "{} {} {}\n", report_theme.rule, path, report_theme.rule)?;
+        for diagnostic in file {
+            let theme = diagnostic.options.box_style.theme();
+            render::write_body_without_path(w, diagnostic, &theme)?;
+            writeln!(w)?;
+        }
+    }
+
+    writeln!(w, "= {}", summary(sorted, &report_theme.labels))
+}
+
+/// Count of each severity present, such as `2 errors, 1 warning found`
+fn summary(diagnostics: &[&Diagnostic], labels: &Labels) -> String {
+    let nouns = [
+        (Severity::Error, labels.error, labels.errors),
+        (Severity::Warning, labels.warning, labels.warnings),
+        (Severity::Info, labels.info, labels.infos),
+        (Severity::Hint, labels.hint, labels.hints),
+    ];
+
+    let counts: Vec<String> = nouns
+        .iter()
+        .filter_map(|&(severity, singular, plural)| {
+            let count = diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count();
+            match count {
+                0 => None,
+                1 => Some(format!("1 {}", singular)),
+                _ => Some(format!("{} {}", count, plural)),
+            }
+        })
+        .collect();
+
+    format!("{} {}", counts.join(", "), labels.found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Suggestion;
+
+    fn diagnostic(file: &str, line: usize, col: usize, message: &'static str) -> Diagnostic {
+        Diagnostic::builder()
+            .source("first line\nsecond line\nthird line")
+            .file(file)
+            .kind("Type Error")
+            .line(line)
+            .col(col)
+            .message(message)
+            .help("help")
+            .build()
+    }
+
+    #[test]
+    fn test_empty_report() {
+        assert_eq!(format_report(&[]), "\n= no issues found\n");
+    }
+
+    #[test]
+    fn test_summary_counts_each_severity() {
+        let with_severity = |severity, line| {
+            let mut diagnostic = diagnostic("a.ts", line, 1, "msg");
+            diagnostic.severity = severity;
+            diagnostic
+        };
+        let report = format_report(&[
+            with_severity(Severity::Error, 1),
+            with_severity(Severity::Warning, 2),
+            with_severity(Severity::Error, 3),
+        ]);
+        assert!(report.ends_with("\n= 2 errors, 1 warning found\n"));
+
+        let report = format_report(&[
+            with_severity(Severity::Hint, 1),
+            with_severity(Severity::Info, 2),
+            with_severity(Severity::Hint, 3),
+        ]);
+        assert!(report.ends_with("\n= 1 info, 2 hints found\n"));
+    }
+
+    #[test]
+    fn test_single_diagnostic_report() {
+        let report = format_report(&[diagnostic("a.ts", 1, 1, "only")]);
+
+        assert!(report.starts_with("\nValidation Error\n❌ Type Error\n"));
+        assert!(report.ends_with("└─\n\n= 1 error found\n"));
+    }
+
+    #[test]
+    fn test_mixed_file_report_is_sorted() {
+        let diagnostics = vec![
+            diagnostic("b.ts", 1, 1, "fourth"),
+            diagnostic("a.ts", 3, 1, "third"),
+            diagnostic("a.ts", 1, 7, "second"),
+            diagnostic("a.ts", 1, 2, "first"),
+        ];
+        let report = format_report(&diagnostics);
+
+        let positions: Vec<usize> = ["first", "second", "third", "fourth"]
+            .iter()
+            .map(|m| report.find(m).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        // One title for the whole report, with each snippet set apart
+        assert_eq!(report.matches("Validation Error").count(), 1);
+        assert_eq!(report.matches("└─\n\n❌").count(), 3);
+        assert!(report.ends_with("= 4 errors found\n"));
+    }
+
+    #[test]
+    fn test_report_title_uses_most_severe() {
+        let mut warning = diagnostic("a.ts", 1, 1, "warn");
+        warning.severity = Severity::Warning;
+
+        let only_warnings = format_report(std::slice::from_ref(&warning));
+        assert!(only_warnings.starts_with("\nValidation Warning\n"));
+
+        let mixed = format_report(&[warning, diagnostic("b.ts", 1, 1, "err")]);
+        assert!(mixed.starts_with("\nValidation Error\n"));
+    }
+
+    #[test]
+    fn test_grouped_report_has_one_header_per_file() {
+        let report = format_grouped_report(&[
+            diagnostic("contracts/b.ts", 2, 1, "four"),
+            diagnostic("contracts/a.ts", 3, 1, "two"),
+            diagnostic("contracts/b.ts", 1, 1, "three"),
+            diagnostic("contracts/a.ts", 1, 2, "one"),
+        ]);
+
+        assert_eq!(report.matches("── contracts/a.ts ──").count(), 1);
+        assert_eq!(report.matches("── contracts/b.ts ──").count(), 1);
+        assert!(report.starts_with("\nValidation Error\n── contracts/a.ts ──\n\n❌ Type Error\n"));
+        assert!(report.ends_with("└─\n\n= 4 errors found\n"));
+
+        // Files alphabetically, then each file's diagnostics by position
+        let positions: Vec<usize> = [
+            "── contracts/a.ts ──",
+            "one",
+            "two",
+            "── contracts/b.ts ──",
+            "three",
+            "four",
+        ]
+        .iter()
+        .map(|m| report.find(m).unwrap())
+        .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        // Boxes under a header only give the position
+        assert!(report.contains("    ┌─ 1:2\n"));
+        assert!(!report.contains("┌─ contracts/"));
+    }
+
+    #[test]
+    fn test_file_report_folds_distant_diagnostics() {
+        let source: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+        let at = |line, message: &'static str| {
+            Diagnostic::builder()
+                .source(&source)
+                .file("a.ts")
+                .line(line)
+                .message(message)
+                .context_lines(2)
+                .build()
+        };
+        let report = format_file_report(&[at(90, "far"), at(10, "near")]);
+
+        // One box for the file, the far gap folded into exactly one row
+        assert_eq!(report.matches("┌─ a.ts:10:1").count(), 1);
+        assert_eq!(report.matches("⋮").count(), 1);
+        assert!(report.contains(" 12 │ line 12\n  ⋮ │\n 88 │ line 88\n"));
+        assert!(report.find("near").unwrap() < report.find("far").unwrap());
+        assert!(report.ends_with("└─\n\n= 2 errors found\n"));
+    }
+
+    #[test]
+    fn test_file_report_joins_close_diagnostics() {
+        let source: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let at = |file, line| {
+            Diagnostic::builder()
+                .source(&source)
+                .file(file)
+                .line(line)
+                .help(format!("help {}", line))
+                .context_lines(1)
+                .build()
+        };
+        let report = format_file_report(&[at("a.ts", 3), at("a.ts", 7), at("b.ts", 3)]);
+
+        // Lines 5 and 6 sit within twice the context, so nothing is folded
+        assert!(!report.contains('⋮'));
+        assert!(report.contains("  4 │ line 4\n  5 │ line 5\n  6 │ line 6\n"));
+        assert!(report.contains("    = help: help 3\n            help 7\n"));
+        assert_eq!(report.matches("┌─").count(), 2);
+    }
+
+    #[test]
+    fn test_file_report_keeps_each_kind_and_fix() {
+        let source = "import { kv } from 'tana/kvs';\nconst w = window;\n";
+        let at = |line, col, kind| {
+            Diagnostic::builder()
+                .source(source)
+                .file("a.ts")
+                .kind(kind)
+                .line(line)
+                .col(col)
+                .build()
+        };
+        let mut import = at(1, 20, "Invalid Import");
+        import.suggestion = Some(Suggestion::new(1, 21, 8, "tana/kv"));
+        let report = format_file_report(&[at(2, 11, "Forbidden Global"), import]);
+
+        // Differing kinds and fixes get a box each, so neither is dropped
+        assert_eq!(report.matches("┌─ a.ts").count(), 2);
+        assert!(report.contains("Invalid Import"));
+        assert!(report.contains("Forbidden Global"));
+        assert!(report.contains("'tana/kv'"));
+        assert!(report.find("Invalid Import").unwrap() < report.find("Forbidden Global").unwrap());
+    }
+
+    #[test]
+    fn test_dedupe_keeps_first_of_each() {
+        let mut first = diagnostic("a.ts", 2, 1, "duplicate");
+        first.help = Some("first help".into());
+        let mut repeat = first.clone();
+        repeat.help = Some("other help".into());
+        let mut diagnostics = vec![first, diagnostic("a.ts", 1, 1, "distinct"), repeat];
+
+        dedupe(&mut diagnostics);
+
+        let kept: Vec<&str> = diagnostics.iter().map(|d| &*d.message).collect();
+        assert_eq!(kept, ["duplicate", "distinct"]);
+        assert_eq!(diagnostics[0].help.as_deref(), Some("first help"));
+    }
+
+    #[test]
+    fn test_dedupe_merging_help_keeps_every_distinct_line() {
+        let mut first = diagnostic("a.ts", 2, 1, "duplicate");
+        first.help = Some("import kv from 'tana/kv'".into());
+        let mut repeat = first.clone();
+        repeat.help = Some("see the kv docs\nimport kv from 'tana/kv'".into());
+        let mut again = first.clone();
+        again.help = None;
+        let mut diagnostics = vec![first, diagnostic("a.ts", 1, 1, "distinct"), repeat, again];
+
+        dedupe_merging_help(&mut diagnostics);
+
+        let kept: Vec<&str> = diagnostics.iter().map(|d| &*d.message).collect();
+        assert_eq!(kept, ["duplicate", "distinct"]);
+        assert_eq!(
+            diagnostics[0].help.as_deref(),
+            Some("import kv from 'tana/kv'\nsee the kv docs")
+        );
+        assert_eq!(diagnostics[1].help.as_deref(), Some("help"));
+    }
+
+    #[test]
+    fn test_limit_not_reached_has_no_notice() {
+        let diagnostics = [
+            diagnostic("a.ts", 1, 1, "one"),
+            diagnostic("a.ts", 2, 1, "two"),
+        ];
+
+        let report = format_report_with_limit(&diagnostics, Some(2));
+        assert_eq!(report, format_report(&diagnostics));
+        assert!(!report.contains("not shown"));
+    }
+
+    #[test]
+    fn test_limit_keeps_first_after_sorting() {
+        let diagnostics = [
+            diagnostic("b.ts", 1, 1, "fourth"),
+            diagnostic("a.ts", 3, 1, "third"),
+            diagnostic("a.ts", 1, 1, "first"),
+            diagnostic("a.ts", 2, 1, "second"),
+        ];
+        let report = format_report_with_limit(&diagnostics, Some(1));
+
+        assert!(report.contains("first"));
+        assert!(!["second", "third", "fourth"]
+            .iter()
+            .any(|m| report.contains(m)));
+        assert!(report.ends_with("└─\n\n= ... and 3 more errors not shown\n= 4 errors found\n"));
+
+        let one_hidden = format_report_with_limit(&diagnostics, Some(3));
+        assert!(one_hidden.contains("= ... and 1 more error not shown\n"));
+    }
+}
@@ -0,0 +1,163 @@
+//! "Did you mean" suggestions for misspelled names
+
+use crate::{text, Diagnostic, ErrorKind};
+
+/// Largest edit distance at which a candidate still counts as a likely typo
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Find the candidate closest to `input`, if any is a plausible typo
+///
+/// Closeness is the Levenshtein edit distance; candidates more than two
+/// edits away are ignored. When several candidates are equally close the
+/// first one wins.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::suggest_closest;
+///
+/// let modules = ["tana/core", "tana/kv", "tana/block"];
+/// assert_eq!(suggest_closest("tana/kvs", &modules), Some("tana/kv".to_string()));
+/// assert_eq!(suggest_closest("lodash", &modules), None);
+/// ```
+pub fn suggest_closest(input: &str, candidates: &[&str]) -> Option<String> {
+    candidates
+        .iter()
+        .map(|candidate| (levenshtein(input, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Build an "Invalid Import" diagnostic for `module`, suggesting a fix
+///
+/// `line` and `col` locate the module specifier (without its quotes) in
+/// `source`. When one of `available` is a likely typo fix, the help reads
+/// `did you mean 'tana/kv'?`; otherwise it lists every available module.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::invalid_import;
+///
+/// let code = "import { kv } from 'tana/kvs';";
+/// let diagnostic = invalid_import(code, "contract.ts", 1, 21, "tana/kvs", &["tana/core", "tana/kv"]);
+///
+/// assert_eq!(diagnostic.help.as_deref(), Some("did you mean 'tana/kv'?"));
+/// ```
+pub fn invalid_import(
+    source: &str,
+    file_path: &str,
+    line: usize,
+    col: usize,
+    module: &str,
+    available: &[&str],
+) -> Diagnostic {
+    let help = match suggest_closest(module, available) {
+        Some(suggestion) => format!("did you mean '{}'?", suggestion),
+        None => format!("Available modules: {}", available.join(", ")),
+    };
+
+    Diagnostic::builder()
+        .source(source)
+        .file(file_path)
+        .kind(ErrorKind::InvalidImport)
+        .line(line)
+        .col(col)
+        .underline(text::width(module))
+        .message(format!("Module '{}' not found", module))
+        .help(help)
+        .build()
+}
+
+/// Number of single-character insertions, deletions, or substitutions
+/// needed to turn `a` into `b`
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_ch) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_ch) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_ch != *b_ch);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULES: [&str; 4] = ["tana/core", "tana/kv", "tana/block", "tana/tx"];
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("tana/kvs", "tana/kv"), 1);
+        assert_eq!(levenshtein("名前", "名"), 1);
+    }
+
+    #[test]
+    fn test_suggest_close_typo() {
+        assert_eq!(
+            suggest_closest("tana/kvs", &MODULES),
+            Some("tana/kv".to_string())
+        );
+        assert_eq!(
+            suggest_closest("tana/blok", &MODULES),
+            Some("tana/block".to_string())
+        );
+    }
+
+    #[test]
+    fn test_suggest_far_off_string() {
+        assert_eq!(suggest_closest("node:fs", &MODULES), None);
+        assert_eq!(suggest_closest("tana/kv", &[]), None);
+    }
+
+    #[test]
+    fn test_suggest_exact_match() {
+        assert_eq!(
+            suggest_closest("tana/tx", &MODULES),
+            Some("tana/tx".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_import_with_suggestion() {
+        let code = "import { kv } from 'tana/kvs';";
+        let rendered = invalid_import(code, "contract.ts", 1, 21, "tana/kvs", &MODULES).render();
+
+        assert!(rendered.contains("❌ Invalid Import"));
+        assert!(rendered.contains("^^^^^^^^ Module 'tana/kvs' not found"));
+        assert!(rendered.contains("= help: did you mean 'tana/kv'?"));
+    }
+
+    #[test]
+    fn test_invalid_import_without_suggestion() {
+        let code = "import fs from 'node:fs';";
+        let diagnostic = invalid_import(code, "contract.ts", 1, 17, "node:fs", &MODULES);
+
+        assert_eq!(
+            diagnostic.help.as_deref(),
+            Some("Available modules: tana/core, tana/kv, tana/block, tana/tx")
+        );
+    }
+
+    #[test]
+    fn test_wide_module_is_underlined_in_display_columns() {
+        let code = "import x from 'ライブ';";
+        let diagnostic = invalid_import(code, "contract.ts", 1, 16, "ライブ", &MODULES);
+
+        assert_eq!(diagnostic.span.length, 6);
+        assert!(diagnostic
+            .render()
+            .contains("  1 │ import x from 'ライブ';\n    │                ^^^^^^ "));
+    }
+}
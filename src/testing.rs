@@ -0,0 +1,123 @@
+//! Helpers for golden-testing rendered diagnostics
+//!
+//! Enabled by the `testing` feature.
+
+/// Make rendered output stable for snapshot comparisons
+///
+/// Strips ANSI color and hyperlink escapes, replaces the emoji icons and
+/// box-drawing characters with their ASCII counterparts (`[error]`, `|`,
+/// `+-`), and trims trailing whitespace from every line. The result is the
+/// same on every platform and terminal.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::testing::normalize_for_snapshot;
+///
+/// let normalized = normalize_for_snapshot("\x1b[31m❌ Type Error\x1b[0m  \n  1 │ x");
+/// assert_eq!(normalized, "[error] Type Error\n  1 | x");
+/// ```
+pub fn normalize_for_snapshot(rendered: &str) -> String {
+    let plain = strip_escapes(rendered);
+    let ascii = ICONS
+        .iter()
+        .chain(GLYPHS)
+        .fold(plain, |text, (from, to)| text.replace(from, to));
+
+    let lines: Vec<&str> = ascii.split('\n').map(str::trim_end).collect();
+    lines.join("\n")
+}
+
+/// Emoji icons and the ASCII labels they normalize to
+const ICONS: &[(&str, &str)] = &[
+    ("❌", "[error]"),
+    ("⚠️", "[warning]"),
+    ("⚠", "[warning]"),
+    ("ℹ️", "[info]"),
+    ("ℹ", "[info]"),
+    ("💡", "[hint]"),
+];
+
+/// Box-drawing characters and the ASCII they normalize to
+const GLYPHS: &[(&str, &str)] = &[
+    ("┌─", "+-"),
+    ("└─", "+-"),
+    ("─", "-"),
+    ("│", "|"),
+    ("…", "..."),
+    ("⋮", "..."),
+];
+
+/// Remove CSI sequences (colors) and OSC sequences (hyperlinks)
+fn strip_escapes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\x1b' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters up to a final byte in `@`..=`~`
+            Some('[') => {
+                for ch in chars.by_ref() {
+                    if ('@'..='~').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            // OSC: up to BEL or the `ESC \` string terminator
+            Some(']') => {
+                while let Some(ch) = chars.next() {
+                    if ch == '\x07' {
+                        break;
+                    }
+                    if ch == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorChoice, Diagnostic, Hyperlinks, Severity};
+
+    #[test]
+    fn test_colored_output_normalizes_to_clean_ascii() {
+        let diagnostic = Diagnostic::builder()
+            .source("import { console } from 'tana/invalid';")
+            .file("/work/contract.ts")
+            .kind("Invalid Import")
+            .col(26)
+            .underline(12)
+            .message("Module 'tana/invalid' not found")
+            .help("Available modules: tana/core, tana/kv")
+            .hyperlinks(Hyperlinks::File)
+            .color(ColorChoice::Always);
+
+        let colored = diagnostic.clone().build().render();
+        assert!(colored.contains('\x1b'));
+
+        let normalized = normalize_for_snapshot(&colored);
+        assert!(normalized.is_ascii());
+        assert!(!normalized.contains('\x1b'));
+        assert!(normalized.lines().all(|line| !line.ends_with(' ')));
+        assert!(normalized.contains("[error] Invalid Import\n\n    +- /work/contract.ts:1:26\n"));
+
+        let warning = diagnostic.severity(Severity::Warning).build().render();
+        assert!(normalize_for_snapshot(&warning).contains("[warning] Invalid Import"));
+    }
+
+    #[test]
+    fn test_plain_ascii_is_unchanged() {
+        assert_eq!(normalize_for_snapshot("a\n  \nb\n"), "a\n\nb\n");
+    }
+}
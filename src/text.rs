@@ -0,0 +1,545 @@
+//! Display-width helpers for laying out source lines in a terminal
+//!
+//! Columns handed to the formatter are counted in characters, but the
+//! rendered box is laid out in terminal cells. These helpers translate
+//! between the two so carets land under the right token. Widths are
+//! measured per grapheme cluster, so a ZWJ emoji sequence or a letter with
+//! combining marks counts as the one visible character it is.
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Number of terminal cells the grapheme `cluster` occupies
+///
+/// Control characters take no space.
+fn cluster_width(cluster: &str) -> usize {
+    let mut chars = cluster.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => ch.width().unwrap_or(0),
+        _ => cluster.width(),
+    }
+}
+
+/// Visible stand-in for the control character `ch`
+///
+/// C0 controls and DEL become their Unicode control pictures (`␛` for ESC),
+/// and C1 controls become `�`.
+fn control_picture(ch: char) -> char {
+    match ch {
+        '\u{0}'..='\u{1F}' => char::from_u32(0x2400 + ch as u32).unwrap_or(ch),
+        '\u{7F}' => '\u{2421}',
+        _ => '\u{FFFD}',
+    }
+}
+
+/// `cluster` as printed in a source line, with control characters shown
+/// as their stand-ins
+fn shown(cluster: &str) -> Cow<'_, str> {
+    if cluster.chars().any(char::is_control) {
+        Cow::Owned(
+            cluster
+                .chars()
+                .map(|ch| {
+                    if ch.is_control() {
+                        control_picture(ch)
+                    } else {
+                        ch
+                    }
+                })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(cluster)
+    }
+}
+
+/// Number of terminal cells `cluster` occupies when printed at `column`
+///
+/// Tabs advance to the next multiple of `tab_width`, and other control
+/// characters take the width of their stand-ins.
+fn cell_width(cluster: &str, column: usize, tab_width: usize) -> usize {
+    if cluster == "\t" {
+        let tab_width = tab_width.max(1);
+        tab_width - column % tab_width
+    } else {
+        cluster_width(&shown(cluster))
+    }
+}
+
+/// Render `line` for display, replacing tabs with spaces up to the next tab
+/// stop and other control characters with visible stand-ins
+///
+/// Binary garbage in the source would otherwise move the cursor or break
+/// the box, and the carets are measured against the stand-ins.
+pub(crate) fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for cluster in line.graphemes(true) {
+        let width = cell_width(cluster, column, tab_width);
+        if cluster == "\t" {
+            expanded.extend(std::iter::repeat_n(' ', width));
+        } else {
+            expanded.push_str(&shown(cluster));
+        }
+        column += width;
+    }
+
+    expanded
+}
+
+/// Display width of the text before `col_num` (1-indexed) in `line`
+///
+/// Columns past the end of the line count as one cell each, so a caret can
+/// still point just beyond the last character. A column inside a grapheme
+/// cluster points at the start of that cluster.
+pub(crate) fn display_offset(line: &str, col_num: usize, tab_width: usize) -> usize {
+    let target = col_num.saturating_sub(1);
+    let mut width = 0;
+    let mut counted = 0;
+
+    for cluster in line.graphemes(true) {
+        let len = cluster.chars().count();
+        if counted + len > target {
+            return width;
+        }
+        width += cell_width(cluster, width, tab_width);
+        counted += len;
+    }
+
+    width + (target - counted)
+}
+
+/// Character column (1-indexed) just past a run of `width` display columns
+/// starting at `col_num` in `line`
+///
+/// The inverse of [`display_offset`]: wide characters and tabs span several
+/// display columns but one character. A run ending inside a wide character
+/// takes all of it, and columns past the end of the line count as one
+/// character each.
+pub(crate) fn col_after_width(line: &str, col_num: usize, width: usize, tab_width: usize) -> usize {
+    let target = display_offset(line, col_num, tab_width) + width;
+    let mut offset = 0;
+    let mut counted = 0;
+
+    for cluster in line.graphemes(true) {
+        if offset >= target {
+            return counted + 1;
+        }
+        offset += cell_width(cluster, offset, tab_width);
+        counted += cluster.chars().count();
+    }
+
+    counted + 1 + target.saturating_sub(offset)
+}
+
+/// Keep a caret run inside a line `line_width` columns wide
+///
+/// The run is cut at the line's last column, and a run starting past the
+/// end becomes a single caret just after the last character. At least one
+/// caret is always shown.
+pub(crate) fn clamp_caret(padding: usize, underline: usize, line_width: usize) -> (usize, usize) {
+    if padding >= line_width {
+        (line_width, 1)
+    } else {
+        (padding, underline.min(line_width - padding).max(1))
+    }
+}
+
+/// Total display width of a line whose tabs are already expanded
+pub(crate) fn width(expanded: &str) -> usize {
+    expanded.graphemes(true).map(cluster_width).sum()
+}
+
+/// Display width of the widest line of `text`
+///
+/// Measured in terminal cells rather than bytes or characters, so a
+/// message in CJK counts each of its characters as the two columns it takes.
+pub(crate) fn widest_line(text: &str) -> usize {
+    text.lines().map(width).max().unwrap_or(0)
+}
+
+/// Whether `ch` belongs to a right-to-left script or forces right-to-left order
+fn is_rtl(ch: char) -> bool {
+    matches!(
+        ch,
+        // Hebrew, Arabic, Syriac, Thaana, NKo, Samaritan, Mandaic, and the
+        // Arabic extensions
+        '\u{0590}'..='\u{08FF}'
+            // Hebrew and Arabic presentation forms
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFC}'
+            // RIGHT-TO-LEFT MARK, EMBEDDING, OVERRIDE and ISOLATE
+            | '\u{200F}'
+            | '\u{202B}'
+            | '\u{202E}'
+            | '\u{2067}'
+    )
+}
+
+/// `text` wrapped in LEFT-TO-RIGHT ISOLATE ... POP DIRECTIONAL ISOLATE when it
+/// contains right-to-left text, so it can't reorder what surrounds it
+pub(crate) fn isolate_rtl(text: &str) -> Cow<'_, str> {
+    if text.chars().any(is_rtl) {
+        Cow::Owned(format!("\u{2066}{}\u{2069}", text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// `text` with its control characters replaced by visible stand-ins
+///
+/// Caller-supplied text could otherwise carry escape sequences that move
+/// the cursor or clear the terminal. Newlines, CRLF line endings and tabs
+/// are kept; other C0 controls and DEL become their Unicode control
+/// pictures (`␛` for ESC), and C1 controls become `�`.
+pub(crate) fn sanitize(text: &str) -> Cow<'_, str> {
+    let is_unsafe = |(index, ch): (usize, char)| match ch {
+        '\n' | '\t' => false,
+        '\r' => !text[index..].starts_with("\r\n"),
+        _ => ch.is_control(),
+    };
+    if !text.char_indices().any(is_unsafe) {
+        return Cow::Borrowed(text);
+    }
+
+    text.char_indices()
+        .map(|(index, ch)| {
+            if is_unsafe((index, ch)) {
+                control_picture(ch)
+            } else {
+                ch
+            }
+        })
+        .collect()
+}
+
+/// Break `line` into rows at most `max_width` display columns wide
+///
+/// Breaks only fall on whitespace, which is collapsed to single spaces, so a
+/// word wider than `max_width` gets a row of its own rather than being split.
+pub(crate) fn wrap(line: &str, max_width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    let mut row = String::new();
+
+    for word in line.split_whitespace() {
+        if !row.is_empty() && width(&row) + 1 + width(word) > max_width {
+            rows.push(std::mem::take(&mut row));
+        }
+        if !row.is_empty() {
+            row.push(' ');
+        }
+        row.push_str(word);
+    }
+
+    if !row.is_empty() || rows.is_empty() {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Horizontal slice of the snippet shown when lines are too wide to print whole
+///
+/// A source row and its caret rows are clipped with the same window, so
+/// their columns stay aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Window {
+    /// First display column (0-indexed) shown after any leading marker
+    start: usize,
+    /// Widest a clipped row may be, markers included
+    max_width: usize,
+    /// Printed where a line was cut, such as `…`
+    marker: &'static str,
+}
+
+impl Window {
+    /// Choose a window of at most `max_width` columns that keeps the focus visible
+    ///
+    /// `line_width` is the width of the line containing the focus, which
+    /// spans `focus_len` columns from `focus_start`. Cuts are marked with
+    /// `marker`.
+    pub(crate) fn around(
+        line_width: usize,
+        focus_start: usize,
+        focus_len: usize,
+        max_width: usize,
+        marker: &'static str,
+    ) -> Self {
+        let marker_width = width(marker);
+        // Room for at least one column of content between two markers
+        let max_width = max_width.max(2 * marker_width + 1);
+        let focus_end = focus_start + focus_len;
+
+        let start = if line_width <= max_width || focus_end <= max_width - marker_width {
+            // The focus fits at the start of the line
+            0
+        } else if focus_start >= line_width - (max_width - marker_width) {
+            // The focus fits in the tail, after a leading marker
+            line_width - (max_width - marker_width)
+        } else {
+            // Center the focus between the two markers
+            let content = max_width - 2 * marker_width;
+            let slack = content - focus_len.min(content);
+            focus_start.saturating_sub(slack / 2).max(1)
+        };
+
+        Self {
+            start,
+            max_width,
+            marker,
+        }
+    }
+
+    /// Whether a line `line_width` columns wide shows anything in the window
+    pub(crate) fn reaches(&self, line_width: usize) -> bool {
+        line_width > self.start
+    }
+
+    /// Columns taken by the leading marker
+    fn lead(&self) -> usize {
+        if self.start > 0 {
+            width(self.marker)
+        } else {
+            0
+        }
+    }
+
+    /// Past-the-end column (in the original line) of what `clip` shows
+    fn visible_end(&self, line_width: usize) -> usize {
+        let available = self.max_width - self.lead();
+        if line_width.saturating_sub(self.start) <= available {
+            line_width
+        } else {
+            // Leave room for the trailing marker
+            self.start + available - width(self.marker)
+        }
+    }
+
+    /// Cut `expanded` down to the window, marking each cut
+    pub(crate) fn clip(&self, expanded: &str) -> String {
+        let line_width = width(expanded);
+        if self.start == 0 && line_width <= self.max_width {
+            return expanded.to_string();
+        }
+
+        let end = self.visible_end(line_width);
+        let mut clipped = String::new();
+        if self.start > 0 {
+            clipped.push_str(self.marker);
+        }
+
+        let mut column = 0;
+        for cluster in expanded.graphemes(true) {
+            let ch_end = column + cluster_width(cluster);
+
+            if column >= self.start && ch_end <= end {
+                clipped.push_str(cluster);
+            } else if column < self.start && ch_end > self.start {
+                // A wide character straddles the cut; keep the columns aligned
+                clipped.extend(std::iter::repeat_n(' ', ch_end.min(end) - self.start));
+            }
+            column = ch_end;
+        }
+
+        if end < line_width {
+            clipped.push_str(self.marker);
+        }
+        clipped
+    }
+
+    /// Move a caret run into the window's coordinates
+    ///
+    /// Returns the new padding and underline length for a run that starts
+    /// `padding` columns into a line `line_width` columns wide.
+    pub(crate) fn caret(
+        &self,
+        padding: usize,
+        underline: usize,
+        line_width: usize,
+    ) -> (usize, usize) {
+        if self.start == 0 && line_width <= self.max_width {
+            return (padding, underline);
+        }
+
+        let end = self.visible_end(line_width).max(padding + 1);
+        let shifted = self.lead() + padding.saturating_sub(self.start);
+        (
+            shifted,
+            underline
+                .min(end.saturating_sub(padding.max(self.start)))
+                .max(1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tabs_to_tab_stops() {
+        assert_eq!(expand_tabs("\tx", 4), "    x");
+        assert_eq!(expand_tabs("ab\tx", 4), "ab  x");
+        assert_eq!(expand_tabs(" \t x", 4), "     x");
+        assert_eq!(expand_tabs("\t\tx", 2), "    x");
+    }
+
+    #[test]
+    fn test_display_offset_counts_wide_and_tab_cells() {
+        assert_eq!(display_offset("abc", 3, 4), 2);
+        assert_eq!(display_offset("名前x", 3, 4), 4);
+        assert_eq!(display_offset("\t x", 3, 4), 5);
+        assert_eq!(display_offset("ab", 5, 4), 4); // past end of line
+    }
+
+    #[test]
+    fn test_col_after_width_inverts_display_offset() {
+        assert_eq!(col_after_width("abc", 1, 2, 4), 3);
+        assert_eq!(col_after_width("'😀';", 1, 4, 4), 4);
+        assert_eq!(col_after_width("名前x", 1, 3, 4), 3); // ends inside 前
+        assert_eq!(col_after_width("\tx", 1, 4, 4), 2);
+        assert_eq!(col_after_width("ab", 2, 3, 4), 5); // past end of line
+    }
+
+    #[test]
+    fn test_display_offset_measures_grapheme_clusters() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let line = format!("{} x", family);
+        assert_eq!(display_offset(&line, 7, 4), 3);
+        assert_eq!(width(&line), 4);
+
+        // A column inside a cluster points at its start
+        assert_eq!(display_offset(&line, 3, 4), 0);
+
+        // A base letter and its combining accent share one cell
+        assert_eq!(display_offset("e\u{301}x", 3, 4), 1);
+        assert_eq!(expand_tabs("e\u{301}\tx", 4), "e\u{301}   x");
+    }
+
+    #[test]
+    fn test_expand_tabs_shows_control_characters() {
+        assert_eq!(expand_tabs("a\x07b\x0b\tc", 4), "a␇b␋    c");
+        assert_eq!(expand_tabs("\x1b[0m\u{7f}\u{85}", 4), "␛[0m␡�");
+        assert_eq!(display_offset("a\x07b", 3, 4), 2);
+        assert_eq!(display_offset("\u{85}\tx", 3, 4), 4);
+    }
+
+    #[test]
+    fn test_isolate_rtl_only_wraps_right_to_left_text() {
+        assert_eq!(isolate_rtl("a\u{FEFF}b"), "a\u{FEFF}b");
+        assert_eq!(isolate_rtl("x = \u{FEFC}"), "\u{2066}x = \u{FEFC}\u{2069}");
+    }
+
+    #[test]
+    fn test_widest_line_counts_display_columns() {
+        assert_eq!(widest_line("名前"), 4);
+        assert_eq!(widest_line("ab\n名前x\nc"), 5);
+        assert_eq!(widest_line(""), 0);
+    }
+
+    #[test]
+    fn test_clamp_caret() {
+        assert_eq!(clamp_caret(2, 3, 10), (2, 3));
+        assert_eq!(clamp_caret(8, 5, 10), (8, 2));
+        assert_eq!(clamp_caret(15, 5, 10), (10, 1));
+        assert_eq!(clamp_caret(0, 0, 0), (0, 1));
+    }
+
+    #[test]
+    fn test_wrap_breaks_on_whitespace() {
+        assert_eq!(wrap("a bb ccc dddd", 6), ["a bb", "ccc", "dddd"]);
+        assert_eq!(
+            wrap("tana/block-explorer x", 5),
+            ["tana/block-explorer", "x"]
+        );
+        assert_eq!(wrap("  spaced   out  ", 80), ["spaced out"]);
+        assert_eq!(wrap("", 10), [""]);
+    }
+
+    #[test]
+    fn test_window_leaves_short_lines_alone() {
+        let window = Window::around(10, 2, 3, 120, "…");
+
+        assert_eq!(window.clip("short line"), "short line");
+        assert_eq!(window.caret(2, 3, 10), (2, 3));
+    }
+
+    #[test]
+    fn test_window_cuts_tail_when_focus_is_early() {
+        let line = "x".repeat(50);
+        let window = Window::around(50, 2, 3, 20, "…");
+        let clipped = window.clip(&line);
+
+        assert_eq!(width(&clipped), 20);
+        assert!(clipped.ends_with('…'));
+        assert!(!clipped.starts_with('…'));
+        assert_eq!(window.caret(2, 3, 50), (2, 3));
+    }
+
+    #[test]
+    fn test_window_cuts_head_when_focus_is_late() {
+        let line = format!("{}target", "x".repeat(94));
+        let window = Window::around(100, 94, 6, 20, "…");
+        let clipped = window.clip(&line);
+
+        assert_eq!(width(&clipped), 20);
+        assert!(clipped.starts_with('…'));
+        assert!(clipped.ends_with("target"));
+
+        let (padding, underline) = window.caret(94, 6, 100);
+        assert_eq!(
+            clipped
+                .chars()
+                .skip(padding)
+                .take(underline)
+                .collect::<String>(),
+            "target"
+        );
+    }
+
+    #[test]
+    fn test_window_cuts_both_sides_around_middle_focus() {
+        let line = format!("{}target{}", "x".repeat(60), "y".repeat(60));
+        let window = Window::around(126, 60, 6, 20, "…");
+        let clipped = window.clip(&line);
+
+        assert_eq!(width(&clipped), 20);
+        assert!(clipped.starts_with('…') && clipped.ends_with('…'));
+
+        let (padding, underline) = window.caret(60, 6, 126);
+        assert_eq!(
+            clipped
+                .chars()
+                .skip(padding)
+                .take(underline)
+                .collect::<String>(),
+            "target"
+        );
+    }
+
+    #[test]
+    fn test_window_keeps_wide_characters_aligned() {
+        let line = format!("{}名前{}", "x".repeat(29), "y".repeat(20));
+        let window = Window::around(53, 31, 2, 6, "…");
+        let clipped = window.clip(&line);
+
+        // The cut lands inside 名, which is replaced by a space
+        assert_eq!(clipped, "… 前y…");
+        assert_eq!(window.caret(31, 2, 53), (2, 2));
+    }
+
+    #[test]
+    fn test_window_with_wide_marker() {
+        let line = format!("{}target{}", "x".repeat(60), "y".repeat(60));
+        let window = Window::around(126, 60, 6, 20, "...");
+        let clipped = window.clip(&line);
+
+        assert_eq!(width(&clipped), 20);
+        assert!(clipped.starts_with("...") && clipped.ends_with("..."));
+
+        let (padding, underline) = window.caret(60, 6, 126);
+        assert_eq!(&clipped[padding..padding + underline], "target");
+    }
+}
@@ -0,0 +1,314 @@
+//! Icons, labels, and box-drawing characters used by the renderer
+
+use crate::{BoxStyle, ColorDepth, Severity};
+
+/// The chrome drawn around a diagnostic
+///
+/// Every piece of fixed text the renderer prints comes from a theme, so a
+/// surface can swap icons, glyphs, or [`Labels`] without touching the layout. Start from
+/// [`Theme::default`] (or [`Theme::ascii`]) and override individual fields.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, Theme};
+///
+/// let theme = Theme {
+///     error_icon: "",
+///     caret_char: '~',
+///     ..Theme::default()
+/// };
+/// let rendered = Diagnostic::builder().source("oops").underline(4).build().render_with(&theme);
+///
+/// assert!(rendered.contains("~~~~"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Theme {
+    /// Icon before the kind of an error; empty for none
+    pub error_icon: &'static str,
+    /// Icon before the kind of a warning; empty for none
+    pub warning_icon: &'static str,
+    /// Icon before the kind of an info diagnostic; empty for none
+    pub info_icon: &'static str,
+    /// Icon before the kind of a hint; empty for none
+    pub hint_icon: &'static str,
+    /// Words framing the diagnostic, such as the title
+    pub labels: Labels,
+    /// Corner opening the location line
+    pub top: &'static str,
+    /// Vertical gutter separator
+    pub gutter_char: char,
+    /// Separator on rows holding nothing else, such as the row under the
+    /// location line; `None` uses `gutter_char` and `' '` leaves those rows
+    /// blank
+    pub empty_gutter_char: Option<char>,
+    /// Corner closing the box
+    pub bottom: &'static str,
+    /// Underline drawn beneath the primary span of an error
+    pub caret_char: char,
+    /// Underline drawn beneath the primary span of a warning
+    pub warning_caret_char: char,
+    /// Underline drawn beneath the primary span of an info diagnostic
+    pub info_caret_char: char,
+    /// Underline drawn beneath the primary span of a hint
+    pub hint_caret_char: char,
+    /// Underline drawn beneath secondary labels
+    pub secondary_caret_char: char,
+    /// Marker printed where a long source line was cut
+    pub ellipsis: &'static str,
+    /// Marker in the line-number column where skipped lines were folded
+    pub fold: &'static str,
+    /// Line drawn on both sides of a file header in a grouped report
+    pub rule: &'static str,
+    /// Palette used for colors when the diagnostic is colored
+    pub color_depth: ColorDepth,
+}
+
+impl Theme {
+    /// Unicode box-drawing characters and emoji icons, as printed by default
+    pub const fn unicode() -> Self {
+        Self {
+            error_icon: "❌",
+            warning_icon: "⚠️",
+            info_icon: "ℹ️",
+            hint_icon: "💡",
+            labels: Labels::english(),
+            top: "┌─",
+            gutter_char: '│',
+            empty_gutter_char: None,
+            bottom: "└─",
+            caret_char: '^',
+            warning_caret_char: '~',
+            info_caret_char: '-',
+            hint_caret_char: '-',
+            secondary_caret_char: '~',
+            ellipsis: "…",
+            fold: "⋮",
+            rule: "──",
+            color_depth: ColorDepth::Ansi16,
+        }
+    }
+
+    /// Plain ASCII (`+-`, `|`, `[error]`) for sinks that mangle Unicode
+    pub const fn ascii() -> Self {
+        Self {
+            error_icon: "[error]",
+            warning_icon: "[warning]",
+            info_icon: "[info]",
+            hint_icon: "[hint]",
+            top: "+-",
+            gutter_char: '|',
+            bottom: "+-",
+            ellipsis: "...",
+            fold: "...",
+            rule: "--",
+            ..Self::unicode()
+        }
+    }
+
+    /// Icon printed before the kind of a diagnostic with `severity`
+    pub fn icon(&self, severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => self.error_icon,
+            Severity::Warning => self.warning_icon,
+            Severity::Info => self.info_icon,
+            Severity::Hint => self.hint_icon,
+        }
+    }
+
+    /// Title printed at the top of a box with `severity`
+    pub fn title(&self, severity: Severity) -> &'static str {
+        self.labels.title(severity)
+    }
+
+    /// Underline drawn beneath the primary span of a diagnostic with `severity`
+    ///
+    /// Errors are underlined with `^^^`, warnings with `~~~`, and info and
+    /// hints with `---`, so they stand apart in a grouped report.
+    pub fn caret(&self, severity: Severity) -> char {
+        match severity {
+            Severity::Error => self.caret_char,
+            Severity::Warning => self.warning_caret_char,
+            Severity::Info => self.info_caret_char,
+            Severity::Hint => self.hint_caret_char,
+        }
+    }
+
+    /// Separator printed on rows holding nothing else
+    pub(crate) fn empty_gutter(&self) -> char {
+        self.empty_gutter_char.unwrap_or(self.gutter_char)
+    }
+
+    /// Label before the help text, such as `= help:`
+    pub(crate) fn help_label(&self) -> String {
+        format!("= {}:", self.labels.help)
+    }
+
+    /// Label before a suggested fix, such as `= suggestion:`
+    pub(crate) fn suggestion_label(&self) -> String {
+        format!("= {}:", self.labels.suggestion)
+    }
+
+    /// Label before the note text, such as `= note:`
+    pub(crate) fn note_label(&self) -> String {
+        format!("= {}:", self.labels.note)
+    }
+
+    /// Label before a related location, such as `= related:`
+    pub(crate) fn related_label(&self) -> String {
+        format!("= {}:", self.labels.related)
+    }
+
+    /// Label before the docs link, such as `= see`
+    pub(crate) fn see_label(&self) -> String {
+        format!("= {}", self.labels.see)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::unicode()
+    }
+}
+
+/// Translatable words in the frame around a diagnostic
+///
+/// Only the framing is localized; messages and help text passed in by the
+/// caller are printed as given. Override fields of [`Labels::english`] to
+/// supply translations.
+///
+/// # Example
+///
+/// ```rust
+/// use tana_validation::{Diagnostic, Labels, Theme};
+///
+/// let theme = Theme {
+///     labels: Labels {
+///         error_title: "Error de validación",
+///         help: "ayuda",
+///         ..Labels::english()
+///     },
+///     ..Theme::default()
+/// };
+/// let rendered = Diagnostic::builder().help("revisa el import").build().render_with(&theme);
+///
+/// assert!(rendered.contains("Error de validación"));
+/// assert!(rendered.contains("= ayuda: revisa el import"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Labels {
+    /// Title opening an error box
+    pub error_title: &'static str,
+    /// Title opening a warning box
+    pub warning_title: &'static str,
+    /// Title opening an info box
+    pub info_title: &'static str,
+    /// Title opening a hint box
+    pub hint_title: &'static str,
+    /// Word introducing the help text, printed as `= {help}:`
+    pub help: &'static str,
+    /// Word introducing the note text, printed as `= {note}:`
+    pub note: &'static str,
+    /// Word introducing a suggested fix, printed as `= {suggestion}:`
+    pub suggestion: &'static str,
+    /// Word introducing a related location, printed as `= {related}:`
+    pub related: &'static str,
+    /// Word introducing the docs link, printed as `= {see} {url}`
+    pub see: &'static str,
+    /// Word introducing the expected type of a mismatch, printed as
+    /// `{expected}:`
+    pub expected: &'static str,
+    /// Word introducing the found type of a mismatch, printed as
+    /// `{found_type}:`
+    pub found_type: &'static str,
+    /// Singular noun for one error
+    pub error: &'static str,
+    /// Plural noun for several errors
+    pub errors: &'static str,
+    /// Singular noun for one warning
+    pub warning: &'static str,
+    /// Plural noun for several warnings
+    pub warnings: &'static str,
+    /// Singular noun for one info diagnostic
+    pub info: &'static str,
+    /// Plural noun for several info diagnostics
+    pub infos: &'static str,
+    /// Singular noun for one hint
+    pub hint: &'static str,
+    /// Plural noun for several hints
+    pub hints: &'static str,
+    /// Word closing a report summary, as in `= 2 errors found`
+    pub found: &'static str,
+    /// Summary of a report with no diagnostics
+    pub no_issues: &'static str,
+    /// Placeholder shown instead of a source line past the end of the file
+    pub line_unavailable: &'static str,
+    /// Notice for diagnostics left out of a capped report, with `{count}`
+    /// and `{noun}` filled in
+    pub not_shown: &'static str,
+    /// Column annotation after a message when columns are shown, with
+    /// `{col}` filled in
+    pub column: &'static str,
+    /// Column annotation for a span covering several lines, with `{col}`
+    /// and `{end}` filled in
+    pub column_span: &'static str,
+}
+
+impl Labels {
+    /// The English labels printed by default
+    pub const fn english() -> Self {
+        Self {
+            error_title: "Validation Error",
+            warning_title: "Validation Warning",
+            info_title: "Validation Info",
+            hint_title: "Validation Hint",
+            help: "help",
+            note: "note",
+            suggestion: "suggestion",
+            related: "related",
+            see: "see",
+            expected: "expected",
+            found_type: "found",
+            error: "error",
+            errors: "errors",
+            warning: "warning",
+            warnings: "warnings",
+            info: "info",
+            infos: "infos",
+            hint: "hint",
+            hints: "hints",
+            found: "found",
+            no_issues: "no issues found",
+            line_unavailable: "<line not available>",
+            not_shown: "... and {count} more {noun} not shown",
+            column: "(col {col})",
+            column_span: "(col {col}, end col {end})",
+        }
+    }
+
+    /// Title printed at the top of a box with `severity`
+    pub fn title(&self, severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => self.error_title,
+            Severity::Warning => self.warning_title,
+            Severity::Info => self.info_title,
+            Severity::Hint => self.hint_title,
+        }
+    }
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl BoxStyle {
+    /// The built-in theme drawn with this style's characters
+    pub fn theme(self) -> Theme {
+        match self {
+            BoxStyle::Unicode => Theme::unicode(),
+            BoxStyle::Ascii => Theme::ascii(),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Structured diagnostics for JavaScript callers that lay out errors themselves
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{format_grouped_report, render, Diagnostic, Severity, Span};
+
+/// The fields of a diagnostic as seen from JavaScript
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiagnosticObject<'a> {
+    kind: &'a str,
+    file: &'a str,
+    line: usize,
+    col: usize,
+    message: &'a str,
+    help: &'a str,
+    underline_length: usize,
+    error_line: &'a str,
+}
+
+/// Describe a validation error as a plain JavaScript object
+///
+/// Takes the same arguments as
+/// [`format_validation_error`](crate::format_validation_error), but instead of
+/// the finished text box returns
+/// `{ kind, file, line, col, message, help, underlineLength, errorLine }` so a
+/// UI can render the error with its own components. `errorLine` is the source
+/// line the error points at, or an empty string if the line doesn't exist.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn get_diagnostic_json(
+    code: &str,
+    file_path: &str,
+    error_kind: &str,
+    line_num: usize,
+    col_num: usize,
+    message: &str,
+    help: &str,
+    underline_length: usize,
+) -> Result<JsValue, JsValue> {
+    let error_line = render::source_window(code, line_num, line_num)
+        .next()
+        .map_or("", |(_, line)| line);
+
+    let object = DiagnosticObject {
+        kind: error_kind,
+        file: file_path,
+        line: line_num,
+        col: col_num,
+        message,
+        help,
+        underline_length,
+        error_line,
+    };
+    serde_wasm_bindgen::to_value(&object).map_err(Into::into)
+}
+
+/// A diagnostic built on the JavaScript side, as accepted by
+/// [`format_report_json`]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DiagnosticInput {
+    source: String,
+    #[serde(alias = "filePath")]
+    file: String,
+    kind: String,
+    code: Option<String>,
+    line: usize,
+    col: usize,
+    underline_length: usize,
+    message: String,
+    help: Option<String>,
+    note: Option<String>,
+    docs_url: Option<String>,
+    severity: String,
+}
+
+impl Default for DiagnosticInput {
+    fn default() -> Self {
+        let diagnostic = Diagnostic::default();
+        Self {
+            source: diagnostic.source,
+            file: diagnostic.file_path,
+            kind: diagnostic.kind.into_owned(),
+            code: None,
+            line: diagnostic.span.line,
+            col: diagnostic.span.col,
+            underline_length: diagnostic.span.length,
+            message: diagnostic.message.into_owned(),
+            help: None,
+            note: None,
+            docs_url: None,
+            severity: Severity::Error.name().to_string(),
+        }
+    }
+}
+
+impl DiagnosticInput {
+    fn into_diagnostic(self) -> Result<Diagnostic, String> {
+        let severity = Severity::ALL
+            .into_iter()
+            .find(|severity| severity.name() == self.severity)
+            .ok_or_else(|| format!("unknown severity '{}'", self.severity))?;
+        Ok(Diagnostic {
+            source: self.source,
+            file_path: self.file,
+            kind: self.kind.into(),
+            code: self.code,
+            span: Span::single_line(self.line, self.col, self.underline_length),
+            message: self.message.into(),
+            help: self.help.map(Into::into),
+            note: self.note,
+            docs_url: self.docs_url,
+            severity,
+            ..Diagnostic::default()
+        })
+    }
+}
+
+/// Render a JSON array of diagnostics as one report grouped by file
+///
+/// Each element is an object with the camelCase fields `source`, `file`
+/// (or `filePath`), `kind`, `code`, `line`, `col`, `underlineLength`,
+/// `message`, `help`, `note`, `docsUrl`, and `severity` (`"error"`,
+/// `"warning"`, `"info"` or `"hint"`); any of them may be left out. The
+/// result is the report of
+/// [`format_grouped_report`](crate::format_grouped_report). Input that
+/// can't be read throws an `Error` in JavaScript whose message starts with
+/// `Invalid diagnostics JSON:` and says what was wrong.
+#[wasm_bindgen]
+pub fn format_report_json(json: &str) -> Result<String, JsValue> {
+    let diagnostics = serde_json::from_str::<Vec<DiagnosticInput>>(json)
+        .map_err(|error| error.to_string())
+        .and_then(|inputs| {
+            inputs
+                .into_iter()
+                .map(DiagnosticInput::into_diagnostic)
+                .collect::<Result<Vec<_>, _>>()
+        });
+
+    match diagnostics {
+        Ok(diagnostics) => Ok(format_grouped_report(&diagnostics)),
+        Err(error) => Err(JsError::new(&format!("Invalid diagnostics JSON: {}", error)).into()),
+    }
+}
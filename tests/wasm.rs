@@ -0,0 +1,92 @@
+#![cfg(target_arch = "wasm32")]
+
+use tana_validation::{format_report_json, get_diagnostic_json};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::*;
+
+fn field(object: &JsValue, name: &str) -> JsValue {
+    js_sys::Reflect::get(object, &JsValue::from_str(name)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_get_diagnostic_json_fields() {
+    let object = get_diagnostic_json(
+        "import { console } from 'tana/invalid';\nexport {};",
+        "contract.ts",
+        "Invalid Import",
+        1,
+        26,
+        "Module 'tana/invalid' not found",
+        "Available modules: tana/core, tana/kv",
+        12,
+    )
+    .unwrap();
+
+    assert_eq!(
+        field(&object, "kind").as_string().unwrap(),
+        "Invalid Import"
+    );
+    assert_eq!(field(&object, "file").as_string().unwrap(), "contract.ts");
+    assert_eq!(field(&object, "line").as_f64(), Some(1.0));
+    assert_eq!(field(&object, "col").as_f64(), Some(26.0));
+    assert_eq!(
+        field(&object, "message").as_string().unwrap(),
+        "Module 'tana/invalid' not found"
+    );
+    assert_eq!(
+        field(&object, "help").as_string().unwrap(),
+        "Available modules: tana/core, tana/kv"
+    );
+    assert_eq!(field(&object, "underlineLength").as_f64(), Some(12.0));
+    assert_eq!(
+        field(&object, "errorLine").as_string().unwrap(),
+        "import { console } from 'tana/invalid';"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_format_report_json_renders_each_diagnostic() {
+    let json = r#"[
+        {
+            "source": "let x = 1;",
+            "file": "b.ts",
+            "kind": "Unused Variable",
+            "line": 1,
+            "col": 5,
+            "message": "'x' is never read",
+            "severity": "warning"
+        },
+        {
+            "source": "import { tx } from 'tana/txs';",
+            "filePath": "a.ts",
+            "kind": "Invalid Import",
+            "line": 1,
+            "col": 21,
+            "underlineLength": 8,
+            "message": "Module 'tana/txs' not found",
+            "help": "did you mean 'tana/tx'?"
+        }
+    ]"#;
+
+    let report = format_report_json(json).unwrap();
+
+    assert!(report.contains("── a.ts ──"));
+    assert!(report.contains("^^^^^^^^ Module 'tana/txs' not found"));
+    assert!(report.contains("── b.ts ──"));
+    assert!(report.contains("^ 'x' is never read"));
+    assert!(report.find("a.ts").unwrap() < report.find("b.ts").unwrap());
+    assert!(report.ends_with("= 1 error, 1 warning found\n"));
+}
+
+#[wasm_bindgen_test]
+fn test_format_report_json_throws_on_invalid_input() {
+    let message = |json: &str| {
+        let error = format_report_json(json).unwrap_err();
+        assert!(error.is_instance_of::<js_sys::Error>());
+        String::from(error.unchecked_into::<js_sys::Error>().message())
+    };
+
+    assert!(message("[{").starts_with("Invalid diagnostics JSON: "));
+    assert!(message(r#"[{"severity": "fatal"}]"#)
+        .starts_with("Invalid diagnostics JSON: unknown severity 'fatal'"));
+}